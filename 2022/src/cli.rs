@@ -0,0 +1,466 @@
+use crate::input::{default_cache_dir, InputService};
+use crate::ledger::Ledger;
+use crate::puzzle::{PartResult, Puzzle, PuzzleId, PARTS};
+use crate::registry::{Registry, Selector};
+use crate::sample::Sample;
+use crate::{compare, logging, page, parallel, runner, snapshot, status, verify, watch};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::path::PathBuf;
+use yansi::Paint;
+
+#[derive(Parser)]
+#[command(about = "Advent of Code")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Directory to cache puzzle inputs in [default: $AOC_INPUT_DIR, or ./inputs]
+    #[arg(long, global = true)]
+    input_dir: Option<PathBuf>,
+    /// adventofcode.com session token. Prefer setting $AOC_SESSION or the session config file,
+    /// since command line arguments are visible to other users
+    #[arg(long, global = true)]
+    session: Option<String>,
+    /// Show the solutions' logs: -v for info, -vv for debug and -vvv for trace
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Log filter directives, as in $RUST_LOG, e.g. day16=trace. Can be given more than once
+    #[arg(long, global = true)]
+    log: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve the given puzzles (DAY, YEAR/DAY or YEAR), or every puzzle if none are given
+    Run {
+        puzzles: Vec<Selector>,
+        /// Solve one part at a time, for more accurate timings
+        #[arg(long)]
+        serial: bool,
+        /// Don't use multiple threads within a single puzzle
+        #[arg(long)]
+        no_parallel: bool,
+        /// Fail if any part goes over its time budget
+        #[arg(long)]
+        strict: bool,
+        #[command(flatten)]
+        inputs: InputArgs,
+    },
+    /// Run the given puzzles' alternative solutions alongside the main ones, and compare their
+    /// answers and timings
+    Compare {
+        puzzles: Vec<Selector>,
+        /// Only run the variants with this name, which can be given more than once
+        #[arg(long = "variant")]
+        variants: Vec<String>,
+        #[command(flatten)]
+        inputs: InputArgs,
+    },
+    /// Solve a puzzle against an input file, which is read as it goes on days that support it so
+    /// that inputs of any size can be used. The answers aren't recorded.
+    Stream {
+        puzzle: Selector,
+        file: PathBuf,
+        /// Only solve this part
+        #[arg(long)]
+        part: Option<u8>,
+    },
+    /// Solve the given puzzles against every teammate's input, and compare the answers with the
+    /// ones in their answers.tsv
+    Verify {
+        puzzles: Vec<Selector>,
+        /// Directory with a subdirectory of inputs for each user [default: the input directory]
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Run a puzzle's tests and solve it, and do it again whenever the sources or its input or
+    /// sample files change. The puzzle needn't be registered yet
+    Watch {
+        puzzle: Selector,
+        /// Solve the example from the puzzle description rather than the real input
+        #[arg(long)]
+        sample: bool,
+        /// Build with optimizations
+        #[arg(long)]
+        release: bool,
+    },
+    /// Show the renders that no longer match their snapshots in the tests, and accept or reject
+    /// them
+    Snapshots {
+        /// Make the new renders the expected ones
+        #[arg(long, conflicts_with = "reject")]
+        accept: bool,
+        /// Throw the new renders away
+        #[arg(long)]
+        reject: bool,
+    },
+    /// Fetch the inputs for the given puzzles again, replacing the cached ones
+    Refresh {
+        #[arg(required = true)]
+        puzzles: Vec<Selector>,
+    },
+    /// Show which puzzles are solved, with their titles and last answers
+    Status {
+        puzzles: Vec<Selector>,
+        /// Fetch the puzzle pages for titles that aren't known yet
+        #[arg(long)]
+        fetch_titles: bool,
+    },
+    /// Mark the last answers to the given puzzles as correct, once adventofcode.com accepts them
+    Accept {
+        #[arg(required = true)]
+        puzzles: Vec<Selector>,
+    },
+    /// Extract the example input and answers from a puzzle page, and print a test module for them
+    Sample {
+        puzzle: Selector,
+        /// Read the puzzle page from this html file instead of the cache
+        #[arg(long)]
+        page: Option<PathBuf>,
+        /// Fetch the puzzle page if it isn't cached yet
+        #[arg(long)]
+        fetch: bool,
+        /// Also write the sample input and answers to files in the input directory
+        #[arg(long)]
+        write: bool,
+    },
+    /// Manage the adventofcode.com session
+    Login {
+        #[command(subcommand)]
+        command: LoginCommand,
+    },
+}
+
+// Where the inputs come from, for commands that solve puzzles. The real inputs are used by default.
+#[derive(Args, Default)]
+struct InputArgs {
+    /// Solve a single puzzle against this file instead of the cached input, or - for stdin.
+    /// The answers aren't recorded
+    #[arg(long, conflicts_with = "sample")]
+    input: Option<PathBuf>,
+    /// Solve against the example from each puzzle description. The answers aren't recorded
+    #[arg(long)]
+    sample: bool,
+}
+
+impl InputArgs {
+    fn is_cached(&self) -> bool {
+        self.input.is_none() && !self.sample
+    }
+}
+
+#[derive(Subcommand)]
+enum LoginCommand {
+    /// Check that the session token is valid
+    Check,
+}
+
+// Solve the puzzles. Answers to the real inputs are recorded in the ledger. It is an error if any
+// part can't be solved, and with `strict`, if any part goes over its time budget.
+fn run(
+    input_service: &InputService,
+    ledger: &mut Ledger,
+    puzzles: &[&'static Puzzle],
+    inputs: InputArgs,
+    serial: bool,
+    strict: bool,
+) -> Result<()> {
+    let record = inputs.is_cached();
+    let (puzzles, inputs) = load_inputs(input_service, puzzles, inputs)?;
+    let results = runner::run(&puzzles, &inputs, serial);
+    let solved: Vec<&PartResult> = results.iter().flatten().collect();
+    if record {
+        for result in &solved {
+            ledger.record(result);
+        }
+        ledger.save()?;
+    }
+    if solved.len() < results.len() {
+        bail!(
+            "{} of {} parts couldn't be solved",
+            results.len() - solved.len(),
+            results.len()
+        );
+    }
+    let over_budget = solved.iter().filter(|r| r.over_budget()).count();
+    match over_budget {
+        0 => Ok(()),
+        _ => {
+            let message = format!(
+                "{} of {} parts went over their time budgets",
+                over_budget,
+                results.len()
+            );
+            if strict {
+                bail!(message);
+            }
+            eprintln!("{}", message);
+            Ok(())
+        }
+    }
+}
+
+// Get the input for each puzzle. Puzzles without a sample are skipped when using samples. A cached
+// input that can't be fetched is an error for that puzzle alone.
+fn load_inputs(
+    input_service: &InputService,
+    puzzles: &[&'static Puzzle],
+    inputs: InputArgs,
+) -> Result<(Vec<&'static Puzzle>, Vec<Result<String>>)> {
+    let mut puzzles = puzzles.to_vec();
+    let inputs = if let Some(path) = inputs.input {
+        let [_] = puzzles[..] else {
+            bail!("--input needs a single puzzle");
+        };
+        vec![Ok(read_input(&path)?)]
+    } else if inputs.sample {
+        puzzles.retain(|p| {
+            if p.sample.is_none() {
+                eprintln!("Skipping {}, which has no sample", p.id());
+            }
+            p.sample.is_some()
+        });
+        puzzles
+            .iter()
+            .flat_map(|p| p.sample)
+            .map(|sample| Ok(sample.to_string()))
+            .collect()
+    } else {
+        let ids: Vec<PuzzleId> = puzzles.iter().map(|p| p.id()).collect();
+        input_service.prefetch(&ids)
+    };
+    Ok((puzzles, inputs))
+}
+
+fn read_input(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        return Ok(input);
+    }
+    fs::read_to_string(path).with_context(|| format!("Unable to read {}", path.display()))
+}
+
+/// Where the command line keeps its files. The default keeps everything under the directory it is
+/// run from, and has no crate for `watch` to rebuild.
+pub struct Config {
+    /// Where inputs and the ledger are cached, unless `--input-dir` is given
+    pub input_dir: PathBuf,
+    /// Where the `snapshots` command looks for renders to review
+    pub snapshot_dir: PathBuf,
+    /// The crate that `watch` tests and runs, which should be the one calling [`main_with`]
+    pub watch_crate: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            input_dir: default_cache_dir(),
+            snapshot_dir: PathBuf::from("snapshots"),
+            watch_crate: None,
+        }
+    }
+}
+
+/// Run the command line with the puzzles from this crate
+pub fn main() -> Result<()> {
+    // watch and snapshots work on this crate's sources
+    let config = Config {
+        snapshot_dir: PathBuf::from(snapshot::SNAPSHOT_DIR),
+        watch_crate: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR"))),
+        ..Config::default()
+    };
+    main_with(crate::registry(), config)
+}
+
+/// Run the command line with the puzzles in `registry`, for crates that add puzzles of their own
+pub fn main_with(registry: Registry, config: Config) -> Result<()> {
+    let cli = Cli::parse();
+    logging::init(cli.verbose, &cli.log);
+    let input_dir = cli.input_dir.unwrap_or(config.input_dir);
+    let mut ledger = Ledger::load(Ledger::file(&input_dir))?;
+    let input_service = InputService::new(input_dir.clone(), cli.session)?;
+    match cli.command {
+        None => run(
+            &input_service,
+            &mut ledger,
+            &registry.select(&[])?,
+            InputArgs::default(),
+            false,
+            false,
+        ),
+        Some(Command::Run {
+            puzzles,
+            serial,
+            no_parallel,
+            strict,
+            inputs,
+        }) => {
+            parallel::set_enabled(!no_parallel);
+            run(
+                &input_service,
+                &mut ledger,
+                &registry.select(&puzzles)?,
+                inputs,
+                serial,
+                strict,
+            )
+        }
+        Some(Command::Compare {
+            puzzles,
+            variants,
+            inputs,
+        }) => {
+            let mut puzzles = registry.select(&puzzles)?;
+            puzzles.retain(|p| compare::has_variants(p, &variants));
+            if puzzles.is_empty() {
+                bail!("None of the puzzles have variants to compare");
+            }
+            let (puzzles, inputs) = load_inputs(&input_service, &puzzles, inputs)?;
+            let inputs = inputs.into_iter().collect::<Result<Vec<_>>>()?;
+            compare::compare(&puzzles, &inputs, &variants)
+        }
+        Some(Command::Stream { puzzle, file, part }) => {
+            let [puzzle] = registry.select(&[puzzle])?[..] else {
+                bail!("Select a single puzzle to stream");
+            };
+            let parts = match part {
+                Some(part) if PARTS.contains(&part) => vec![part],
+                Some(part) => bail!("There is no part {}", part),
+                None => PARTS.to_vec(),
+            };
+            // Each part reads the whole file again
+            for part in parts {
+                let input = File::open(&file)
+                    .with_context(|| format!("Unable to read {}", file.display()))?;
+                println!("{}", puzzle.solve_reader(part, &mut BufReader::new(input))?);
+            }
+            Ok(())
+        }
+        Some(Command::Verify { puzzles, dir }) => {
+            let dir = dir.unwrap_or_else(|| input_dir.clone());
+            verify::verify(&registry.select(&puzzles)?, &dir)
+        }
+        Some(Command::Watch {
+            puzzle,
+            sample,
+            release,
+        }) => {
+            let year = registry
+                .latest_year()
+                .ok_or(anyhow!("No puzzles registered"))?;
+            let Some(crate_dir) = config.watch_crate else {
+                bail!("There is no crate to watch. Set Config::watch_crate to the puzzles' crate");
+            };
+            let options = watch::Options {
+                crate_dir,
+                input_dir,
+                sample,
+                release,
+            };
+            watch::watch(puzzle.id(year)?, options)
+        }
+        Some(Command::Snapshots { accept, reject }) => {
+            snapshot::review(&config.snapshot_dir, accept, reject)
+        }
+        Some(Command::Status {
+            puzzles,
+            fetch_titles,
+        }) => {
+            let puzzles = registry.select(&puzzles)?;
+            // Only look at the puzzle pages for titles that aren't written down
+            let ids: Vec<PuzzleId> = puzzles
+                .iter()
+                .filter(|p| p.title.is_none())
+                .map(|p| p.id())
+                .collect();
+            let mut pages = input_service.get_pages(&ids, fetch_titles).into_iter();
+            // A page that can't be fetched just leaves its title out
+            let titles: Vec<Option<String>> = puzzles
+                .iter()
+                .map(|p| match p.title {
+                    Some(title) => Some(title.to_string()),
+                    None => match pages.next()? {
+                        Ok(page) => page.and_then(|page| page::title(&page)),
+                        Err(e) => {
+                            eprintln!("{}", Paint::red(format!("{:#}", e)));
+                            None
+                        }
+                    },
+                })
+                .collect();
+            status::print_status(&puzzles, &titles, &ledger);
+            Ok(())
+        }
+        Some(Command::Accept { puzzles }) => {
+            for puzzle in registry.select(&puzzles)? {
+                for part in PARTS {
+                    if !ledger.accept(puzzle.id(), part) {
+                        bail!("{} part {} hasn't been run yet", puzzle.id(), part);
+                    }
+                }
+            }
+            ledger.save()
+        }
+        Some(Command::Refresh { puzzles }) => {
+            let puzzles = registry.select(&puzzles)?;
+            let ids: Vec<PuzzleId> = puzzles.iter().map(|p| p.id()).collect();
+            let results = input_service.refresh(&ids)?;
+            let failed: Vec<anyhow::Error> = results.into_iter().filter_map(Result::err).collect();
+            for e in &failed {
+                println!("{}", Paint::red(format!("{:#}", e)));
+            }
+            if !failed.is_empty() {
+                bail!(
+                    "{} of {} inputs couldn't be fetched",
+                    failed.len(),
+                    ids.len()
+                );
+            }
+            Ok(())
+        }
+        Some(Command::Sample {
+            puzzle,
+            page,
+            fetch,
+            write,
+        }) => {
+            let year = registry
+                .latest_year()
+                .ok_or(anyhow!("No puzzles registered"))?;
+            let id = puzzle.id(year)?;
+            let html = match page {
+                Some(path) => fs::read_to_string(&path)
+                    .with_context(|| format!("Unable to read {}", path.display()))?,
+                None => input_service
+                    .get_pages(&[id], fetch)
+                    .remove(0)?
+                    .ok_or(anyhow!(
+                        "The page for {} isn't cached. Pass --fetch or --page",
+                        id
+                    ))?,
+            };
+            let sample = Sample::from_page(&html)?;
+            if write {
+                for file in sample.write_fixtures(&input_dir, id)? {
+                    eprintln!("Wrote {}", file.display());
+                }
+            }
+            print!("{}", sample.test_stub(id));
+            Ok(())
+        }
+        Some(Command::Login {
+            command: LoginCommand::Check,
+        }) => {
+            let year = registry
+                .latest_year()
+                .ok_or(anyhow!("No puzzles registered"))?;
+            let session = input_service.check_login(year)?;
+            println!("Logged in using the session token from {}", session.source);
+            Ok(())
+        }
+    }
+}
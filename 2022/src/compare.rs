@@ -0,0 +1,191 @@
+use crate::memory::{self, Allocations};
+use crate::puzzle::{PartResult, Puzzle, PARTS};
+use crate::status::short;
+use crate::util::format_bytes;
+use anyhow::{bail, Result};
+use std::time::{Duration, Instant};
+use yansi::Paint;
+
+// One solution to a part, as run by compare
+pub(crate) struct Row {
+    // "main" for the puzzle's own solution, otherwise the name of the variant
+    pub(crate) name: &'static str,
+    pub(crate) answer: String,
+    // Both include the parse stage, which every variant does for itself
+    pub(crate) duration: Duration,
+    pub(crate) allocations: Option<Allocations>,
+}
+
+impl Row {
+    fn new(name: &'static str, result: PartResult) -> Row {
+        Row {
+            name,
+            answer: result.answer,
+            duration: result.duration,
+            allocations: result.allocations,
+        }
+    }
+}
+
+// Solve a part with the main solution and then each variant whose name is in `names`, or every
+// variant if `names` is empty. They are run one at a time so that the timings are comparable.
+pub(crate) fn compare_part(
+    puzzle: &Puzzle,
+    part: u8,
+    input: &str,
+    names: &[String],
+) -> Result<Vec<Row>> {
+    // The main solution is timed and measured from parsing to answer, just like the variants
+    let start = Instant::now();
+    let (main, allocations) =
+        memory::measure(|| puzzle.solve_prepared(part, &puzzle.prepare(input)?));
+    let main = PartResult {
+        duration: start.elapsed(),
+        allocations,
+        ..main?
+    };
+    let mut rows = vec![Row::new("main", main)];
+    for variant in puzzle.variants(part) {
+        if names.is_empty() || names.iter().any(|name| name == variant.name) {
+            rows.push(Row::new(
+                variant.name,
+                puzzle.solve_variant(variant, input)?,
+            ));
+        }
+    }
+    Ok(rows)
+}
+
+// How many times longer than the main solution a row took. The main solution can be too quick to
+// time at all.
+fn ratio(duration: Duration, main: Duration) -> String {
+    if main.is_zero() {
+        return "-".to_string();
+    }
+    format!("{:.2}x", duration.as_secs_f64() / main.as_secs_f64())
+}
+
+// Whether the puzzle has any variants that compare would run
+pub(crate) fn has_variants(puzzle: &Puzzle, names: &[String]) -> bool {
+    puzzle
+        .variants
+        .iter()
+        .any(|v| names.is_empty() || names.iter().any(|name| name == v.name))
+}
+
+// Compare the variants of each puzzle with its main solution, and print their answers and
+// timings side by side. It is an error if any of them disagree.
+pub(crate) fn compare(puzzles: &[&Puzzle], inputs: &[String], names: &[String]) -> Result<()> {
+    let mut disagreements = 0;
+    for (puzzle, input) in puzzles.iter().zip(inputs) {
+        print!("{} day {}", puzzle.year, puzzle.day);
+        if let Some(title) = puzzle.title {
+            print!(": {}", title);
+        }
+        println!();
+        for part in PARTS {
+            let rows = compare_part(puzzle, part, input, names)?;
+            if rows.len() == 1 {
+                continue;
+            }
+            println!("  part {}", part);
+            let width = rows.iter().map(|r| r.name.len()).max().unwrap_or(0);
+            let main = &rows[0];
+            for row in &rows {
+                let glyph = if row.answer == main.answer {
+                    Paint::yellow('*')
+                } else {
+                    disagreements += 1;
+                    Paint::red('x')
+                };
+                // The most memory each one needed, when built with the alloc-stats feature
+                let peak = row
+                    .allocations
+                    .map(|a| format!(" {:>10}", format_bytes(a.peak)))
+                    .unwrap_or_default();
+                println!(
+                    "    {} {:width$} {:>10.3} ms {:>8}{} {}",
+                    glyph,
+                    row.name,
+                    row.duration.as_micros() as f64 / 1000.0,
+                    ratio(row.duration, main.duration),
+                    peak,
+                    short(&row.answer)
+                );
+            }
+        }
+        println!();
+    }
+    if disagreements > 0 {
+        bail!(
+            "{} variants disagreed with the main solution",
+            disagreements
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::compare::{compare_part, has_variants, ratio};
+    use crate::puzzle::PuzzleFn::{I32, USIZE};
+    use crate::puzzle::{Puzzle, Variant};
+    use std::time::Duration;
+
+    const LINES: Puzzle = Puzzle {
+        year: 2022,
+        day: 26,
+        part1: USIZE(|input| Ok(input.lines().count())),
+        part2: I32(|input| Ok(input.lines().map(|l| l.len() as i32).sum())),
+        variants: &[
+            Variant {
+                name: "split",
+                part: 1,
+                solve: USIZE(|input| Ok(input.split('\n').count())),
+            },
+            Variant {
+                name: "bytes",
+                part: 1,
+                solve: USIZE(|input| Ok(input.bytes().filter(|&b| b == b'\n').count())),
+            },
+        ],
+        ..Puzzle::DEFAULT
+    };
+
+    #[test]
+    fn compare_part_test() {
+        let answers = |names: &[&str]| {
+            let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+            compare_part(&LINES, 1, "a\nb\nc", &names)
+                .unwrap()
+                .into_iter()
+                .map(|row| (row.name, row.answer))
+                .collect::<Vec<_>>()
+        };
+        // Counting newlines misses the last line, which doesn't end with one
+        assert_eq!(
+            answers(&[]),
+            vec![
+                ("main", "3".to_string()),
+                ("split", "3".to_string()),
+                ("bytes", "2".to_string())
+            ]
+        );
+        assert_eq!(
+            answers(&["bytes"]),
+            vec![("main", "3".to_string()), ("bytes", "2".to_string())]
+        );
+        assert_eq!(compare_part(&LINES, 2, "a\nb", &[]).unwrap().len(), 1);
+
+        assert!(has_variants(&LINES, &[]));
+        assert!(!has_variants(&LINES, &["unknown".to_string()]));
+        assert!(!has_variants(&Puzzle::DEFAULT, &[]));
+    }
+
+    #[test]
+    fn ratio_test() {
+        let ms = Duration::from_millis;
+        assert_eq!(ratio(ms(3), ms(2)), "1.50x");
+        assert_eq!(ratio(ms(3), Duration::ZERO), "-");
+    }
+}
@@ -1,76 +1,85 @@
 use crate::puzzle::Puzzle;
-use crate::puzzle::PuzzleFn::I32;
+use crate::puzzle::PuzzleFn::STREAM;
+use crate::util::LineReader;
+use anyhow::{Context, Result};
 use std::cmp::max;
-use std::collections::BinaryHeap;
+use std::io::BufRead;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 1,
-    part1: I32(count_calories),
-    part2: I32(top_3),
+    title: Some("Calorie Counting"),
+    part1: STREAM(|input| Ok(count_calories(input)?.to_string())),
+    part2: STREAM(|input| Ok(top_3(input)?.to_string())),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
+// A blank line separates one elf's items from the next
+pub(crate) fn parse_calories(line: &str) -> Result<Option<u64>> {
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let calories = line
+        .parse()
+        .with_context(|| format!("Unable to parse line: {}", line))?;
+    Ok(Some(calories))
+}
+
+// Totals are kept in a u64, since stress test inputs can be much bigger than the real ones
+fn add_calories(total: u64, calories: u64) -> Result<u64> {
+    total
+        .checked_add(calories)
+        .context("Too many calories to count")
+}
+
 // Part 1
-fn count_calories(input: &str) -> i32 {
+fn count_calories(input: &mut dyn BufRead) -> Result<u64> {
     let mut max_sum = 0;
     let mut cum_sum = 0;
 
-    for line in input.lines() {
-        if line.is_empty() {
-            cum_sum = 0;
-        } else {
-            let calories: i32 = line
-                .parse()
-                .unwrap_or_else(|_| panic!("Unable to parse line: {}", line));
-            cum_sum += calories;
+    let mut lines = LineReader::new(input);
+    while let Some(line) = lines.next_line()? {
+        match parse_calories(line)? {
+            None => cum_sum = 0,
+            Some(calories) => cum_sum = add_calories(cum_sum, calories)?,
         }
         max_sum = max(max_sum, cum_sum);
     }
 
-    max_sum
+    Ok(max_sum)
 }
 
 // Part 2
-fn top_3(input: &str) -> i32 {
-    let mut elf_heap = BinaryHeap::new();
+fn top_3(input: &mut dyn BufRead) -> Result<u64> {
+    // Only the top 3 totals are kept, smallest first
+    let mut top = [0; 3];
     let mut cum_sum = 0;
 
-    for line in input.lines() {
-        if line.is_empty() {
-            elf_heap.push(cum_sum);
-            cum_sum = 0;
-        } else {
-            let calories: i32 = line
-                .parse()
-                .unwrap_or_else(|_| panic!("Unable to parse line: {}", line));
-            cum_sum += calories;
+    let mut lines = LineReader::new(input);
+    while let Some(line) = lines.next_line()? {
+        match parse_calories(line)? {
+            None => {
+                push_top_3(&mut top, cum_sum);
+                cum_sum = 0;
+            }
+            Some(calories) => cum_sum = add_calories(cum_sum, calories)?,
         }
     }
-    elf_heap.push(cum_sum);
+    push_top_3(&mut top, cum_sum);
 
-    let mut sum = 0;
-    for _ in 0..3 {
-        sum += elf_heap.pop().unwrap();
-    }
-    sum
+    top.into_iter().try_fold(0, add_calories)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::day01::{count_calories, top_3};
-
-    #[test]
-    fn count_calories_test() {
-        let result = count_calories(SAMPLE_INPUT);
-        assert_eq!(result, 24000);
-    }
-
-    #[test]
-    fn top_3_test() {
-        let result = top_3(SAMPLE_INPUT);
-        assert_eq!(result, 45000);
+fn push_top_3(top: &mut [u64; 3], total: u64) {
+    if total > top[0] {
+        top[0] = total;
+        top.sort_unstable();
     }
+}
 
-    const SAMPLE_INPUT: &str = "1000
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "1000
 2000
 3000
 
@@ -84,4 +93,65 @@ mod tests {
 9000
 
 10000";
+
+#[cfg(test)]
+mod tests {
+    use crate::day01::{count_calories, top_3, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::{differential, puzzle_tests};
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    puzzle_tests! {
+        PUZZLE;
+        count_calories_test: 1, SAMPLE_INPUT => 24000;
+        top_3_test: 2, SAMPLE_INPUT => 45000;
+    }
+
+    // At least three elves, each carrying a few items
+    fn elves() -> impl Strategy<Value = String> {
+        vec(vec(1..100000u64, 1..8), 3..20).prop_map(|elves: Vec<Vec<u64>>| {
+            elves
+                .iter()
+                .map(|items| items.iter().map(|c| c.to_string()).join("\n"))
+                .join("\n\n")
+        })
+    }
+
+    // The total carried by each elf, largest first
+    fn totals(input: &str) -> Vec<u64> {
+        let mut totals: Vec<u64> = input
+            .split("\n\n")
+            .map(|elf| elf.lines().map(|c| c.parse::<u64>().unwrap()).sum())
+            .collect();
+        totals.sort_unstable_by(|a, b| b.cmp(a));
+        totals
+    }
+
+    #[test]
+    fn count_calories_differential() {
+        differential(
+            elves(),
+            |input| totals(input)[0],
+            |input| count_calories(&mut input.as_bytes()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn big_totals_test() {
+        let input = format!("{}\n{}\n", i32::MAX, i32::MAX);
+        let total = count_calories(&mut input.as_bytes()).unwrap();
+        assert_eq!(total, 2 * i32::MAX as u64);
+        let input = format!("{}\n1\n", u64::MAX);
+        assert!(count_calories(&mut input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn top_3_differential() {
+        differential(
+            elves(),
+            |input| totals(input)[..3].iter().sum(),
+            |input| top_3(&mut input.as_bytes()).unwrap(),
+        );
+    }
 }
@@ -1,50 +1,67 @@
 use crate::day02::RPC::{PAPER, ROCK, SCISSORS};
 use crate::day02::WLD::{DRAW, LOSE, WIN};
 
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::puzzle::{Puzzle, PuzzleFn::STREAM};
+use crate::util::LineReader;
+use anyhow::{bail, Result};
+use std::io::BufRead;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 2,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Rock Paper Scissors"),
+    part1: STREAM(|input| Ok(part1(input)?.to_string())),
+    part2: STREAM(|input| Ok(part2(input)?.to_string())),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-fn part1(input: &str) -> i32 {
-    input
-        .lines()
-        .map(|line| {
-            let mut chars = line.chars();
-            let opponent = parse_rpc(chars.next().unwrap());
-            let me = parse_rpc(chars.nth(1).unwrap());
-            score(opponent, me)
-        })
-        .sum()
+fn part1(input: &mut dyn BufRead) -> Result<u64> {
+    total_score(input, |opponent, me| {
+        score(parse_rpc(opponent), parse_rpc(me))
+    })
 }
 
-fn part2(input: &str) -> i32 {
-    input
-        .lines()
-        .map(|line| {
-            let mut chars = line.chars();
-            let opponent = parse_rpc(chars.next().unwrap());
-            let me_wld = parse_wld(chars.nth(1).unwrap());
-            let me = match me_wld {
-                WIN => match opponent {
-                    ROCK => PAPER,
-                    PAPER => SCISSORS,
-                    SCISSORS => ROCK,
-                },
-                LOSE => match opponent {
-                    ROCK => SCISSORS,
-                    PAPER => ROCK,
-                    SCISSORS => PAPER,
-                },
-                DRAW => opponent,
-            };
+fn part2(input: &mut dyn BufRead) -> Result<u64> {
+    total_score(input, |opponent, me| {
+        let opponent = parse_rpc(opponent);
+        let me_wld = parse_wld(me);
+        let me = match me_wld {
+            WIN => match opponent {
+                ROCK => PAPER,
+                PAPER => SCISSORS,
+                SCISSORS => ROCK,
+            },
+            LOSE => match opponent {
+                ROCK => SCISSORS,
+                PAPER => ROCK,
+                SCISSORS => PAPER,
+            },
+            DRAW => opponent,
+        };
 
-            score(opponent, me)
-        })
-        .sum()
+        score(opponent, me)
+    })
+}
+
+// The sum of the score of each round in the strategy guide. A round scores at most 9, but a stress
+// test input can have enough rounds to overflow an i32.
+fn total_score(input: &mut dyn BufRead, round_score: impl Fn(char, char) -> u64) -> Result<u64> {
+    let mut total = 0;
+    let mut lines = LineReader::new(input);
+    while let Some(line) = lines.next_line()? {
+        let (opponent, me) = parse_round(line)?;
+        total += round_score(opponent, me);
+    }
+    Ok(total)
+}
+
+// A line of the strategy guide, e.g. `A Y`. Returns the opponent's letter and ours.
+pub(crate) fn parse_round(line: &str) -> Result<(char, char)> {
+    match line.as_bytes() {
+        [opponent @ b'A'..=b'C', b' ', me @ b'X'..=b'Z'] => Ok((*opponent as char, *me as char)),
+        _ => bail!("Can't parse: {}", line),
+    }
 }
 
 fn parse_rpc(c: char) -> RPC {
@@ -82,7 +99,7 @@ enum WLD {
     DRAW,
 }
 
-fn score(opponent: RPC, me: RPC) -> i32 {
+fn score(opponent: RPC, me: RPC) -> u64 {
     let (lose, draw, win) = (0, 3, 6);
     match me {
         ROCK => {
@@ -109,23 +126,19 @@ fn score(opponent: RPC, me: RPC) -> i32 {
     }
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "A Y
+B X
+C Z";
+
 #[cfg(test)]
 mod test {
-    use crate::day02::{part1, part2};
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 15)
-    }
+    use crate::day02::{PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
 
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 12)
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 15;
+        part2_test: 2, SAMPLE_INPUT => 12;
     }
-
-    const SAMPLE_INPUT: &str = "A Y
-B X
-C Z";
 }
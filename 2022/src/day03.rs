@@ -1,24 +1,39 @@
-use anyhow::{Context, Result};
-use itertools::Itertools;
+use anyhow::{bail, Context, Result};
 use std::collections::HashSet;
+use std::io::BufRead;
 
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::puzzle::{Puzzle, PuzzleFn::STREAM};
+use crate::util::LineReader;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 3,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Rucksack Reorganization"),
+    part1: STREAM(|input| Ok(part1(input)?.to_string())),
+    part2: STREAM(|input| Ok(part2(input)?.to_string())),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-fn part1(input: &str) -> i32 {
-    input
-        .lines()
-        .map(|line| {
-            let mistake = find_mistake(line)?;
-            priority(mistake)
-        })
-        .map(Result::unwrap)
-        .sum()
+fn part1(input: &mut dyn BufRead) -> Result<u64> {
+    let mut sum = 0;
+    let mut lines = LineReader::new(input);
+    while let Some(line) = lines.next_line()? {
+        let mistake = find_mistake(parse_rucksack(line)?)?;
+        sum += priority(mistake)?;
+    }
+    Ok(sum)
+}
+
+// Each rucksack is a list of items, split evenly between its two compartments
+pub(crate) fn parse_rucksack(line: &str) -> Result<&str> {
+    if !line.chars().all(|c| c.is_ascii_alphabetic()) {
+        bail!("Unexpected item in {}", line);
+    }
+    if !line.len().is_multiple_of(2) {
+        bail!("Compartments of {} aren't the same size", line);
+    }
+    Ok(line)
 }
 
 fn find_mistake(line: &str) -> Result<char> {
@@ -37,33 +52,55 @@ fn into_set(s: &str) -> HashSet<char> {
     HashSet::from_iter(s.chars())
 }
 
-fn part2(input: &str) -> i32 {
-    input
-        .lines()
-        .chunks(3)
-        .into_iter()
-        .map(|mut chunk| {
-            let first = into_set(chunk.next().unwrap());
-            let second = into_set(chunk.next().unwrap());
-            let mut third = chunk.next().unwrap().chars();
-            let badge = third
-                .find(|c| first.contains(c) && second.contains(c))
-                .context("No badge found")?;
-            priority(badge)
-        })
-        .map(Result::unwrap)
-        .sum()
+fn part2(input: &mut dyn BufRead) -> Result<u64> {
+    let mut sum = 0;
+    // The items in the first two rucksacks of the current group
+    let mut group: Vec<HashSet<char>> = Vec::with_capacity(2);
+    let mut lines = LineReader::new(input);
+    while let Some(line) = lines.next_line()? {
+        let rucksack = parse_rucksack(line)?;
+        let [first, second] = &group[..] else {
+            group.push(into_set(rucksack));
+            continue;
+        };
+        let badge = rucksack
+            .chars()
+            .find(|c| first.contains(c) && second.contains(c))
+            .context("No badge found")?;
+        sum += priority(badge)?;
+        group.clear();
+    }
+    if !group.is_empty() {
+        bail!("Expected a group of 3 elves");
+    }
+    Ok(sum)
 }
 
-fn priority(c: char) -> Result<i32> {
+// Sums of priorities are kept in a u64, since stress test inputs can be much bigger than the real ones
+fn priority(c: char) -> Result<u64> {
     let priorities = ('a'..='z').chain('A'..='Z').collect::<Vec<_>>();
     let priority = priorities.iter().position(|&p| p == c);
-    Ok(priority.context(format!("Unable to parse {}", c))? as i32 + 1)
+    Ok(priority.context(format!("Unable to parse {}", c))? as u64 + 1)
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
 #[cfg(test)]
 mod test {
-    use crate::day03::{part1, part2, priority};
+    use crate::day03::{priority, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 157;
+        part2_test: 2, SAMPLE_INPUT => 70;
+    }
 
     #[test]
     fn priority_test() {
@@ -72,23 +109,4 @@ mod test {
         assert_eq!(priority('A').unwrap(), 27);
         assert_eq!(priority('Z').unwrap(), 52);
     }
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 157)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 70)
-    }
-
-    const SAMPLE_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw";
 }
@@ -1,28 +1,51 @@
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
-use std::str::FromStr;
+use crate::puzzle::{Puzzle, PuzzleFn::STREAM};
+use crate::util::LineReader;
+use anyhow::{anyhow, Result};
+use std::io::BufRead;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 4,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Camp Cleanup"),
+    part1: STREAM(|input| Ok(part1(input)?.to_string())),
+    part2: STREAM(|input| Ok(part2(input)?.to_string())),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-fn part1(input: &str) -> i32 {
-    input
-        .lines()
-        .map(|line| parse_elves(line))
-        .filter(|(a, b)| contains(a, b) || contains(b, a))
-        .count() as i32
+fn part1(input: &mut dyn BufRead) -> Result<i32> {
+    count_pairs(input, |a, b| contains(a, b) || contains(b, a))
 }
 
-fn parse_elves(line: &str) -> ((i32, i32), (i32, i32)) {
-    let mut elves = line.split(',').map(|s| split_i32('-', s));
-    (elves.next().unwrap(), elves.next().unwrap())
+// The number of pairs of elves for which `f` is true
+fn count_pairs(
+    input: &mut dyn BufRead,
+    f: impl Fn(&(i32, i32), &(i32, i32)) -> bool,
+) -> Result<i32> {
+    let mut count = 0;
+    let mut lines = LineReader::new(input);
+    while let Some(line) = lines.next_line()? {
+        let (a, b) = parse_elves(line)?;
+        if f(&a, &b) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+// The pair of section ranges on a line, e.g. `2-4,6-8`
+pub(crate) fn parse_elves(line: &str) -> Result<((i32, i32), (i32, i32))> {
+    let (a, b) = line
+        .split_once(',')
+        .ok_or(anyhow!("Expected two elves in {}", line))?;
+    Ok((split_i32('-', a)?, split_i32('-', b)?))
 }
 
-fn split_i32(delimiter: char, s: &str) -> (i32, i32) {
-    let mut digits = s.split(delimiter).map(|x| i32::from_str(x).unwrap());
-    (digits.next().unwrap(), digits.next().unwrap())
+fn split_i32(delimiter: char, s: &str) -> Result<(i32, i32)> {
+    let (a, b) = s
+        .split_once(delimiter)
+        .ok_or(anyhow!("Expected a range but got {}", s))?;
+    Ok((a.parse()?, b.parse()?))
 }
 
 // Returns true if range b is completely contains by range a
@@ -30,12 +53,8 @@ fn contains(a: &(i32, i32), b: &(i32, i32)) -> bool {
     a.0 <= b.0 && a.1 >= b.1
 }
 
-fn part2(input: &str) -> i32 {
-    input
-        .lines()
-        .map(|line| parse_elves(line))
-        .filter(|(a, b)| overlaps(a, b))
-        .count() as i32
+fn part2(input: &mut dyn BufRead) -> Result<i32> {
+    count_pairs(input, overlaps)
 }
 
 // Returns true if ranges overlap at all
@@ -43,26 +62,22 @@ fn overlaps(a: &(i32, i32), b: &(i32, i32)) -> bool {
     a.1 >= b.0 && a.0 <= b.1
 }
 
-#[cfg(test)]
-mod test {
-    use crate::day04::{part1, part2};
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 2)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 4)
-    }
-
-    const SAMPLE_INPUT: &str = "2-4,6-8
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "2-4,6-8
 2-3,4-5
 5-7,7-9
 2-8,3-7
 6-6,4-6
 2-6,4-8";
+
+#[cfg(test)]
+mod test {
+    use crate::day04::{PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 2;
+        part2_test: 2, SAMPLE_INPUT => 4;
+    }
 }
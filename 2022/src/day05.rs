@@ -1,106 +1,156 @@
 use crate::puzzle::{Puzzle, PuzzleFn::STR};
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::util::parse_all;
+use anyhow::{anyhow, bail, Result};
+use nom::bytes::complete::tag;
+use nom::character::complete::u32 as nom_u32;
+use nom::sequence::{preceded, tuple};
 use std::str::FromStr;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 5,
+    title: Some("Supply Stacks"),
     part1: STR(part1),
     part2: STR(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
 type Stacks = Vec<Vec<char>>;
 
-fn part1(input: &str) -> String {
-    let (mut stacks, instructions) = parse_input(input);
+fn part1(input: &str) -> Result<String> {
+    let (mut stacks, instructions) = parse_input(input)?;
     for instr in instructions {
         for _ in 0..instr.n_crates {
-            let crate_to_move = stacks.get_mut(instr.from - 1).unwrap().pop().unwrap();
-            stacks.get_mut(instr.to - 1).unwrap().push(crate_to_move);
+            let crate_to_move = take_crate(&mut stacks, instr.from)?;
+            stacks[instr.to - 1].push(crate_to_move);
         }
     }
-    top_stacks(stacks)
+    Ok(top_stacks(stacks))
 }
 
-fn parse_input(input: &str) -> (Stacks, Vec<Instruction>) {
-    let mut split_input = input.split("\n\n");
-    let stacks = parse_stacks(split_input.next().unwrap());
-    let instructions = split_input
-        .next()
-        .unwrap()
+// Stacks are numbered from 1, and parse_input has checked that they exist
+fn take_crate(stacks: &mut Stacks, stack: usize) -> Result<char> {
+    stacks[stack - 1]
+        .pop()
+        .ok_or_else(|| anyhow!("Stack {} is empty", stack))
+}
+
+pub(crate) fn parse_input(input: &str) -> Result<(Stacks, Vec<Instruction>)> {
+    let (drawing, instructions) = input
+        .split_once("\n\n")
+        .ok_or(anyhow!("Expected a blank line after the stacks"))?;
+    let stacks = parse_stacks(drawing)?;
+    let instructions: Vec<Instruction> = instructions
         .lines()
-        .map(|line| line.parse::<Instruction>().unwrap())
-        .collect();
-    (stacks, instructions)
+        .map(|line| line.parse())
+        .collect::<Result<_>>()?;
+    for instr in &instructions {
+        for stack in [instr.from, instr.to] {
+            if !(1..=stacks.len()).contains(&stack) {
+                bail!("There is no stack {}", stack);
+            }
+        }
+    }
+    Ok((stacks, instructions))
 }
 
 fn top_stacks(stacks: Vec<Vec<char>>) -> String {
     let mut top_stacks: Vec<char> = vec![];
+    // An empty stack has nothing on top
     for mut stack in stacks {
-        top_stacks.push(stack.pop().unwrap())
+        top_stacks.extend(stack.pop())
     }
     top_stacks.into_iter().collect()
 }
 
-fn parse_stacks(input: &str) -> Vec<Vec<char>> {
+// Trailing spaces may be missing from the drawing, so short lines are fine
+fn parse_stacks(input: &str) -> Result<Vec<Vec<char>>> {
     let mut reversed = input.lines().rev();
-    let indices = reversed.next().unwrap();
+    let indices = reversed.next().ok_or(anyhow!("No stacks found"))?;
     let n_stacks = (indices.len() + 1) / 4;
     let mut stacks: Vec<Vec<char>> = vec![];
     stacks.resize_with(n_stacks, Vec::new);
     for line in reversed {
         let chars: Vec<char> = line.chars().collect();
-        for stack in 0..n_stacks {
-            let c = chars.get(stack * 4 + 1).unwrap();
-            if c.is_ascii_uppercase() {
-                stacks.get_mut(stack).unwrap().push(*c);
+        for (stack, crates) in stacks.iter_mut().enumerate() {
+            match chars.get(stack * 4 + 1) {
+                Some(c) if c.is_ascii_uppercase() => crates.push(*c),
+                Some(' ') | None => {}
+                Some(c) => bail!("Unexpected crate {} in {}", c, line),
             }
         }
     }
-    stacks
+    Ok(stacks)
 }
 
-struct Instruction {
+pub(crate) struct Instruction {
     n_crates: usize,
     from: usize,
     to: usize,
 }
 
 impl FromStr for Instruction {
-    type Err = ();
-
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
-        }
-        let caps = RE.captures(s).unwrap();
+    type Err = anyhow::Error;
+
+    // move 1 from 2 to 1
+    fn from_str(s: &str) -> Result<Self> {
+        let (n_crates, from, to) = parse_all(
+            tuple((
+                preceded(tag("move "), nom_u32),
+                preceded(tag(" from "), nom_u32),
+                preceded(tag(" to "), nom_u32),
+            )),
+            s,
+        )?;
         Ok(Instruction {
-            n_crates: caps.get(1).unwrap().as_str().parse().unwrap(),
-            from: caps.get(2).unwrap().as_str().parse().unwrap(),
-            to: caps.get(3).unwrap().as_str().parse().unwrap(),
+            n_crates: n_crates as usize,
+            from: from as usize,
+            to: to as usize,
         })
     }
 }
 
-fn part2(input: &str) -> String {
-    let (mut stacks, instructions) = parse_input(input);
+fn part2(input: &str) -> Result<String> {
+    let (mut stacks, instructions) = parse_input(input)?;
     let mut flipper: Vec<char> = vec![];
     for instr in instructions {
         for _ in 0..instr.n_crates {
-            let crate_to_move = stacks.get_mut(instr.from - 1).unwrap().pop().unwrap();
+            let crate_to_move = take_crate(&mut stacks, instr.from)?;
             flipper.push(crate_to_move);
         }
         for _ in 0..instr.n_crates {
             let crate_to_move = flipper.pop().unwrap();
-            stacks.get_mut(instr.to - 1).unwrap().push(crate_to_move);
+            stacks[instr.to - 1].push(crate_to_move);
         }
     }
-    top_stacks(stacks)
+    Ok(top_stacks(stacks))
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
 #[cfg(test)]
 mod test {
-    use crate::day05::{parse_stacks, part1, part2, Instruction};
+    use crate::day05::{parse_stacks, part1, part2, Instruction, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::{differential, puzzle_tests};
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => "CMZ";
+        part2_test: 2, SAMPLE_INPUT => "MCD";
+    }
 
     #[test]
     fn parse_stacks_test() {
@@ -108,7 +158,7 @@ mod test {
 [N] [C]    
 [Z] [M] [P]
  1   2   3 ";
-        let mut stacks = parse_stacks(stack_input);
+        let mut stacks = parse_stacks(stack_input).unwrap();
         assert_eq!(stacks.len(), 3);
         assert_eq!(stacks.get(1).unwrap().len(), 3);
         assert_eq!(stacks.get_mut(0).unwrap().pop().unwrap(), 'N');
@@ -124,25 +174,68 @@ mod test {
         assert_eq!(instr.to, 9);
     }
 
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, "CMZ")
+    // Random crate stacks, followed by moves that always leave at least one crate on each stack
+    fn crate_stacks() -> impl Strategy<Value = String> {
+        let stacks = vec(vec(prop::char::range('A', 'Z'), 1..8), 2..10);
+        let moves = vec((any::<usize>(), any::<usize>(), any::<usize>()), 0..30);
+        (stacks, moves).prop_map(|(mut stacks, moves)| {
+            let drawing = draw_stacks(&stacks);
+            let mut instructions = vec![];
+            for (n, from, to) in moves {
+                let from = from % stacks.len();
+                let to = (from + 1 + to % (stacks.len() - 1)) % stacks.len();
+                if stacks[from].len() < 2 {
+                    continue;
+                }
+                let height = stacks[from].len();
+                let n = 1 + n % (height - 1);
+                let moved = stacks[from].split_off(height - n);
+                stacks[to].extend(moved);
+                instructions.push(format!("move {} from {} to {}", n, from + 1, to + 1));
+            }
+            format!("{}\n\n{}", drawing, instructions.join("\n"))
+        })
     }
 
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, "MCD")
+    // Draw the stacks the way the puzzle does, with the bottom crates on the last line
+    fn draw_stacks(stacks: &[Vec<char>]) -> String {
+        let height = stacks.iter().map(|s| s.len()).max().unwrap();
+        let mut lines: Vec<String> = (0..height)
+            .rev()
+            .map(|level| {
+                stacks
+                    .iter()
+                    .map(|s| {
+                        s.get(level)
+                            .map_or("   ".to_string(), |c| format!("[{}]", c))
+                    })
+                    .join(" ")
+            })
+            .collect();
+        lines.push((1..=stacks.len()).map(|i| format!(" {} ", i)).join(" "));
+        lines.join("\n")
     }
 
-    const SAMPLE_INPUT: &str = "    [D]    
-[N] [C]    
-[Z] [M] [P]
- 1   2   3 
+    // Moving crates one at a time is the same as moving several at once, one by one
+    fn one_at_a_time(input: &str) -> String {
+        let (drawing, moves) = input.split_once("\n\n").unwrap();
+        let moves = moves
+            .lines()
+            .flat_map(|line| {
+                let instr = line.parse::<Instruction>().unwrap();
+                let single = format!("move 1 from {} to {}", instr.from, instr.to);
+                std::iter::repeat_n(single, instr.n_crates)
+            })
+            .join("\n");
+        format!("{}\n\n{}", drawing, moves)
+    }
 
-move 1 from 2 to 1
-move 3 from 1 to 3
-move 2 from 2 to 1
-move 1 from 1 to 2";
+    #[test]
+    fn crate_mover_differential() {
+        differential(
+            crate_stacks(),
+            |input| part2(&one_at_a_time(input)).unwrap(),
+            |input| part1(input).unwrap(),
+        );
+    }
 }
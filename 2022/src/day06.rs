@@ -1,56 +1,162 @@
-use std::collections::HashSet;
+use anyhow::{bail, Context, Result};
+use std::collections::VecDeque;
+use std::io::{BufRead, Bytes, Read};
 
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::puzzle::{Puzzle, PuzzleFn::STREAM};
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 6,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Tuning Trouble"),
+    part1: STREAM(|input| Ok(first_marker(input, 4)?.to_string())),
+    part2: STREAM(|input| Ok(first_marker(input, 14)?.to_string())),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-fn part1(input: &str) -> i32 {
-    let mut cursor = 0;
-    let mut buffer = [' '; 4];
-    for (i, c) in input.chars().enumerate() {
-        buffer[cursor] = c;
-        cursor = (cursor + 1) % 4;
-        let set = HashSet::from(buffer);
-        if !set.contains(&' ') && set.len() == 4 {
-            return (i + 1) as i32;
-        }
+fn first_marker(input: &mut dyn BufRead, window: usize) -> Result<usize> {
+    find_marker(input, window)?.context("No marker found")
+}
+
+// The number of bytes read when the last `window` of them are first all different
+pub(crate) fn find_marker(input: &mut dyn BufRead, window: usize) -> Result<Option<usize>> {
+    Ok(markers(input, window)?.next().transpose()?)
+}
+
+// Every position at which the last `window` bytes are all different. The datastream is read a
+// byte at a time, since it is all on one line, and ends at the line ending.
+pub(crate) fn markers(input: &mut dyn BufRead, window: usize) -> Result<Markers<&mut dyn BufRead>> {
+    if window == 0 {
+        bail!("A marker can't be empty");
     }
-    -1
+    Ok(Markers {
+        bytes: Read::bytes(input),
+        window,
+        buffer: VecDeque::with_capacity(window + 1),
+        counts: [0; 256],
+        repeated: 0,
+        position: 0,
+        ended: false,
+    })
+}
+
+// A sliding window over the datastream that keeps a count of each byte in the window, so that
+// each step takes constant time however big the window is
+pub(crate) struct Markers<R> {
+    bytes: Bytes<R>,
+    window: usize,
+    buffer: VecDeque<u8>,
+    counts: [usize; 256],
+    // The number of distinct bytes that appear more than once in the window
+    repeated: usize,
+    position: usize,
+    ended: bool,
 }
 
-fn part2(input: &str) -> i32 {
-    let mut cursor = 0;
-    let mut buffer = [' '; 14];
-    for (i, c) in input.chars().enumerate() {
-        buffer[cursor] = c;
-        cursor = (cursor + 1) % 14;
-        let set = HashSet::from(buffer);
-        if !set.contains(&' ') && set.len() == 14 {
-            return (i + 1) as i32;
+impl<R: Read> Iterator for Markers<R> {
+    type Item = std::io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
         }
+        for byte in self.bytes.by_ref() {
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(e) => return Some(Err(e)),
+            };
+            if byte == b'\n' || byte == b'\r' {
+                break;
+            }
+            self.position += 1;
+            self.buffer.push_back(byte);
+            self.counts[byte as usize] += 1;
+            if self.counts[byte as usize] == 2 {
+                self.repeated += 1;
+            }
+            if self.buffer.len() > self.window {
+                let old = self.buffer.pop_front().unwrap() as usize;
+                if self.counts[old] == 2 {
+                    self.repeated -= 1;
+                }
+                self.counts[old] -= 1;
+            }
+            if self.buffer.len() == self.window && self.repeated == 0 {
+                return Some(Ok(self.position));
+            }
+        }
+        self.ended = true;
+        None
     }
-    -1
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
 #[cfg(test)]
 mod test {
-    use crate::day06::{part1, part2};
+    use crate::day06::{find_marker, markers, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::{differential, puzzle_tests};
+    use itertools::Itertools;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 7;
+        part1_more_test: 1, "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg" => 10;
+        part2_test: 2, SAMPLE_INPUT => 19;
+        part2_more_test: 2, "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw" => 26;
+    }
 
     #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 7)
+    fn find_marker_test() {
+        assert_eq!(find_marker(&mut "aab".as_bytes(), 2).unwrap(), Some(3));
+        assert_eq!(find_marker(&mut "aaaa".as_bytes(), 2).unwrap(), None);
+        assert_eq!(find_marker(&mut "abc".as_bytes(), 4).unwrap(), None);
+        assert_eq!(
+            find_marker(&mut [0xff, 0, 0xff].as_slice(), 2).unwrap(),
+            Some(2)
+        );
     }
 
     #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 19)
+    fn markers_test() {
+        let all = |input: &str, window| {
+            markers(&mut input.as_bytes(), window)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(all("abcabba", 3), vec![3, 4, 5]);
+        // The line ending isn't part of the datastream
+        assert_eq!(all("abca\n", 2), vec![2, 3, 4]);
+        assert_eq!(all("abca\r\nbc", 2), vec![2, 3, 4]);
+        assert!(markers(&mut "abc".as_bytes(), 0).is_err());
     }
 
-    const SAMPLE_INPUT: &str = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+    // Every marker position for windows of 1 to 6 bytes
+    fn all_markers(input: &str, find: impl Fn(&str, usize) -> Vec<usize>) -> Vec<Vec<usize>> {
+        (1..=6).map(|window| find(input, window)).collect()
+    }
+
+    #[test]
+    fn markers_differential() {
+        differential(
+            "[a-f]{0,40}",
+            |input| {
+                all_markers(input, |input, window| {
+                    (window..=input.len())
+                        .filter(|&end| input[end - window..end].bytes().all_unique())
+                        .collect()
+                })
+            },
+            |input| {
+                all_markers(input, |input, window| {
+                    markers(&mut input.as_bytes(), window)
+                        .unwrap()
+                        .map(Result::unwrap)
+                        .collect()
+                })
+            },
+        );
+    }
 }
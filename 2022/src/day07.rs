@@ -1,19 +1,39 @@
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
-use nom::branch::alt;
+use crate::puzzle::{Puzzle, PuzzleFn::U64};
+use crate::util::parse_all;
+use anyhow::{bail, Context, Result};
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, anychar};
-use nom::sequence::pair;
-use nom::sequence::preceded;
+use nom::character::complete::{char, not_line_ending, u64};
+use nom::combinator::verify;
+use nom::sequence::{preceded, separated_pair};
 use nom::IResult;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::iter;
+use tracing::warn;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 7,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("No Space Left On Device"),
+    part1: U64(part1),
+    part2: U64(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
+// A file or directory name, which can contain anything but a slash
+fn name(i: &str) -> IResult<&str, &str> {
+    verify(not_line_ending, |name: &str| {
+        !name.is_empty() && !name.contains('/') && name != "." && name != ".."
+    })(i)
+}
+
+// The argument to cd, which can be a relative or absolute path
 fn cd(i: &str) -> IResult<&str, &str> {
-    preceded(tag("$ cd "), alt((tag("/"), tag(".."), alpha1)))(i)
+    preceded(
+        tag("$ cd "),
+        verify(not_line_ending, |path: &str| !path.is_empty()),
+    )(i)
 }
 
 fn ls(i: &str) -> IResult<&str, &str> {
@@ -21,161 +41,340 @@ fn ls(i: &str) -> IResult<&str, &str> {
 }
 
 fn dir(i: &str) -> IResult<&str, &str> {
-    let (input, name) = preceded(tag("dir "), alpha1)(i)?;
-    Ok((input, name))
+    preceded(tag("dir "), name)(i)
 }
 
-fn file(i: &str) -> IResult<&str, File> {
-    let (input, (size, name)) = pair(nom::character::complete::i32, anychar)(i)?;
-    Ok((
-        input,
-        File {
-            _name: name.to_string(),
-            size,
-        },
-    ))
+fn file(i: &str) -> IResult<&str, (u64, &str)> {
+    separated_pair(u64, char(' '), name)(i)
 }
 
-struct Directory<'a> {
-    path: Vec<&'a str>,
-    name: &'a str,
-    // originally I tried including recursive references to directories,
-    // but the borrowing got too complicated
-    // Instead, we'll just record the list of directory names
-    dir_names: Vec<&'a str>,
-    files: Vec<File>,
+// An index into the nodes of a `FileSystem`
+pub(crate) type NodeId = usize;
+
+const ROOT: NodeId = 0;
+
+pub(crate) struct Node {
+    pub(crate) name: String,
+    pub(crate) parent: Option<NodeId>,
+    pub(crate) kind: NodeKind,
 }
 
-impl<'a> Directory<'a> {
-    fn add_directory(&mut self, dir: &'a str) {
-        self.dir_names.push(dir);
+pub(crate) enum NodeKind {
+    // Children are kept in name order, which is the order the puzzle lists them in
+    Directory(BTreeMap<String, NodeId>),
+    File(u64),
+}
+
+impl Node {
+    pub(crate) fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Directory(_))
     }
+}
 
-    fn add_file(&mut self, file: File) {
-        self.files.push(file);
+// Every file and directory seen in a terminal transcript, stored in a single list and linked by
+// index. A node is always added after its parent.
+pub(crate) struct FileSystem {
+    nodes: Vec<Node>,
+    // The total size of every node, worked out once the whole transcript has been replayed
+    sizes: Vec<u64>,
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        let root = Node {
+            name: "/".to_string(),
+            parent: None,
+            kind: NodeKind::Directory(BTreeMap::new()),
+        };
+        FileSystem {
+            nodes: vec![root],
+            sizes: vec![],
+        }
     }
 
-    fn file_size(&self) -> i32 {
-        self.files.iter().map(|f| f.size).sum()
+    pub(crate) fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
     }
 
-    fn size(&self, directories: &Vec<Directory>) -> i32 {
-        let subdir_size: i32 = directories
-            .iter()
-            .filter(|d| {
-                if d.path.is_empty() {
-                    false
-                } else {
-                    let (dir_name, dir_path) = d.path.split_last().unwrap();
-                    dir_name == &self.name && dir_path == &self.path
-                }
-            })
-            .map(|d| d.size(directories))
-            .sum();
-        self.file_size() + subdir_size
+    // The node with the given name in a directory
+    fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[dir].kind {
+            NodeKind::Directory(children) => children.get(name).copied(),
+            NodeKind::File(_) => None,
+        }
+    }
+
+    // Add a node to a directory, which mustn't already have a node with the same name
+    fn add(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            kind,
+        });
+        if let NodeKind::Directory(children) = &mut self.nodes[parent].kind {
+            children.insert(name.to_string(), id);
+        }
+        id
+    }
+
+    fn set_file_size(&mut self, id: NodeId, size: u64) {
+        self.nodes[id].kind = NodeKind::File(size);
+    }
+
+    // The full path of a node, e.g. `/a/e`
+    pub(crate) fn path(&self, id: NodeId) -> String {
+        let mut names: Vec<&str> = iter::successors(Some(id), |&id| self.nodes[id].parent)
+            .take_while(|&id| id != ROOT)
+            .map(|id| self.nodes[id].name.as_str())
+            .collect();
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Work out the size of every node. Made up transcripts can have files too big to add up.
+    fn total_sizes(&mut self) -> Result<()> {
+        let mut sizes = vec![0u64; self.nodes.len()];
+        // Children come after their parents, so this sees each directory's whole contents before
+        // adding it to its parent
+        for (id, node) in self.nodes.iter().enumerate().rev() {
+            if let NodeKind::File(size) = node.kind {
+                sizes[id] = size;
+            }
+            if let Some(parent) = node.parent {
+                sizes[parent] = sizes[parent]
+                    .checked_add(sizes[id])
+                    .with_context(|| format!("{} is too big to add up", self.path(parent)))?;
+            }
+        }
+        self.sizes = sizes;
+        Ok(())
+    }
+
+    // The size of a file, or the total size of everything in a directory
+    pub(crate) fn size(&self, id: NodeId) -> u64 {
+        self.sizes[id]
+    }
+
+    // Every node along with its depth below the root, depth first in name order
+    fn walk(&self) -> impl Iterator<Item = (NodeId, usize)> + '_ {
+        let mut stack = vec![(ROOT, 0)];
+        iter::from_fn(move || {
+            let (id, depth) = stack.pop()?;
+            if let NodeKind::Directory(children) = &self.nodes[id].kind {
+                stack.extend(children.values().rev().map(|&child| (child, depth + 1)));
+            }
+            Some((id, depth))
+        })
+    }
+
+    // The nodes matching a predicate, like `find /`
+    pub(crate) fn find<'a>(
+        &'a self,
+        predicate: impl Fn(NodeId) -> bool + 'a,
+    ) -> impl Iterator<Item = NodeId> + 'a {
+        self.walk()
+            .map(|(id, _)| id)
+            .filter(move |&id| predicate(id))
+    }
+
+    // The path and total size of every directory, like `du`
+    pub(crate) fn du(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        self.find(|id| self.node(id).is_dir())
+            .map(|id| (self.path(id), self.size(id)))
     }
 }
 
-struct File {
-    _name: String,
-    size: i32,
+// Draws the tree the way the puzzle does
+impl Display for FileSystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (id, depth) in self.walk() {
+            let node = self.node(id);
+            write!(f, "{:indent$}- {} ", "", node.name, indent = depth * 2)?;
+            match node.kind {
+                NodeKind::Directory(_) => writeln!(f, "(dir)")?,
+                NodeKind::File(size) => writeln!(f, "(file, size={})", size)?,
+            }
+        }
+        Ok(())
+    }
 }
 
-fn build_directories(input: &str) -> Vec<Directory> {
-    let mut path: Vec<&str> = vec!["/"];
-    let mut directories: Vec<Directory> = vec![];
+// Something in a transcript that doesn't add up. These are worked around rather than treated as
+// errors, since the rest of the transcript is still useful.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Diagnostic {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
 
-    let mut lines = input.lines();
-    let mut line = lines.next().unwrap();
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
 
-    'outer: loop {
-        if let Ok((_, dir)) = cd(line) {
-            if dir == ".." {
-                path.pop();
-            } else if dir != "/" {
-                path.push(dir);
-            }
-            line = lines.next().unwrap();
-        } else if let Ok((_, _)) = ls(line) {
-            let (dir_name, dir_path) = path.split_last().unwrap();
-            let mut cwd = Directory {
-                path: dir_path.to_vec(),
-                name: dir_name,
-                dir_names: vec![],
-                files: vec![],
-            };
-            loop {
-                match lines.next() {
-                    Some(ls_line) => {
-                        if let Ok((_, file)) = file(ls_line) {
-                            cwd.add_file(file);
-                        } else if let Ok((_, dir)) = dir(ls_line) {
-                            cwd.add_directory(dir);
-                        } else if let Ok((_, _)) = cd(ls_line) {
-                            // if not a file or directory, save the current directory and restart the main loop
-                            directories.push(cwd);
-                            line = ls_line;
-                            break; // go back to outer loop
-                        } else {
-                            panic!("Can't parse {}", ls_line.to_string())
-                        }
+// The state of the shell while a transcript is replayed
+struct Replay {
+    fs: FileSystem,
+    cwd: NodeId,
+    // Whether the lines being read are the output of ls
+    listing: bool,
+    // Directories that ls has been run in
+    listed: HashSet<NodeId>,
+    line: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Replay {
+    fn warn(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic {
+            line: self.line,
+            message,
+        });
+    }
+
+    // Follow a path one name at a time, like a real shell would. Directories that weren't listed
+    // are created as they are entered.
+    fn cd(&mut self, path: &str) {
+        if path.starts_with('/') {
+            self.cwd = ROOT;
+        }
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            match name {
+                "." => {}
+                ".." => match self.fs.node(self.cwd).parent {
+                    Some(parent) => self.cwd = parent,
+                    None => self.warn("cd .. from /, which stays in /".to_string()),
+                },
+                name => match self.fs.child(self.cwd, name) {
+                    Some(id) if self.fs.node(id).is_dir() => self.cwd = id,
+                    Some(id) => {
+                        let message =
+                            format!("Can't cd into {}, which is a file", self.fs.path(id));
+                        self.warn(message);
+                        return;
                     }
                     None => {
-                        directories.push(cwd);
-                        break 'outer; // No more lines so end outer loop
+                        if self.listed.contains(&self.cwd) {
+                            let message = format!(
+                                "cd into {}, which ls didn't show in {}",
+                                name,
+                                self.fs.path(self.cwd)
+                            );
+                            self.warn(message);
+                        }
+                        let dir = NodeKind::Directory(BTreeMap::new());
+                        self.cwd = self.fs.add(self.cwd, name, dir);
                     }
+                },
+            }
+        }
+    }
+
+    // A line of ls output. Listing a directory again only adds what wasn't there before, and if a
+    // file's size has changed the latest size is kept.
+    fn entry(&mut self, name: &str, kind: NodeKind) {
+        let Some(existing) = self.fs.child(self.cwd, name) else {
+            self.fs.add(self.cwd, name, kind);
+            return;
+        };
+        let path = self.fs.path(existing);
+        match (&self.fs.node(existing).kind, kind) {
+            (NodeKind::Directory(_), NodeKind::Directory(_)) => {}
+            (&NodeKind::File(old), NodeKind::File(new)) => {
+                if old != new {
+                    self.warn(format!("{} was {} bytes, and is now {}", path, old, new));
+                    self.fs.set_file_size(existing, new);
                 }
             }
-        } else {
-            panic!("Can't parse {}", line.to_string())
+            _ => self.warn(format!(
+                "{} is listed as both a file and a directory, so the second is ignored",
+                path
+            )),
         }
     }
-    directories
 }
 
-fn part1(input: &str) -> i32 {
-    let directories = build_directories(input);
-    directories
-        .iter()
-        .map(|d| d.size(&directories))
-        .filter(|d| d <= &100000)
-        .sum()
+// Replay the commands in a terminal transcript to find out what is on the disk. Lines that can't
+// be parsed are errors, but anything inconsistent is reported in the diagnostics instead.
+pub(crate) fn parse_input(input: &str) -> Result<(FileSystem, Vec<Diagnostic>)> {
+    if input.trim().is_empty() {
+        bail!("Empty transcript");
+    }
+    let mut replay = Replay {
+        fs: FileSystem::new(),
+        cwd: ROOT,
+        listing: false,
+        listed: HashSet::new(),
+        line: 0,
+        diagnostics: vec![],
+    };
+    for (i, line) in input.lines().enumerate() {
+        replay.line = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Ok(path) = parse_all(cd, line) {
+            replay.listing = false;
+            replay.cd(path);
+        } else if parse_all(ls, line).is_ok() {
+            replay.listing = true;
+            replay.listed.insert(replay.cwd);
+        } else if let Ok(name) = parse_all(dir, line) {
+            if !replay.listing {
+                replay.warn(format!("{} isn't part of the output of ls", line));
+            }
+            replay.entry(name, NodeKind::Directory(BTreeMap::new()));
+        } else if let Ok((size, name)) = parse_all(file, line) {
+            if !replay.listing {
+                replay.warn(format!("{} isn't part of the output of ls", line));
+            }
+            replay.entry(name, NodeKind::File(size));
+        } else {
+            bail!("Can't parse line {}: {}", replay.line, line);
+        }
+    }
+    replay.fs.total_sizes()?;
+    Ok((replay.fs, replay.diagnostics))
 }
 
-const TOTAL_DISK_SPACE: i32 = 70000000;
-const REQUIRED_DISK_SPACE: i32 = 30000000;
-
-fn part2(input: &str) -> i32 {
-    let directories = build_directories(input);
-    let root = directories.iter().find(|d| d.name == "/").unwrap();
-    let free_space = TOTAL_DISK_SPACE - root.size(&directories);
-    let needed_space = REQUIRED_DISK_SPACE - free_space;
-    // println!("needed space: {}", needed_space);
-    directories
-        .iter()
-        .map(|d| d.size(&directories))
-        .filter(|d| d >= &needed_space)
-        .min()
-        .unwrap()
+// Replay a transcript, showing anything odd about it
+fn load(input: &str) -> Result<FileSystem> {
+    let (fs, diagnostics) = parse_input(input)?;
+    for diagnostic in diagnostics {
+        warn!("Odd transcript, {}", diagnostic);
+    }
+    Ok(fs)
 }
 
-#[cfg(test)]
-mod test {
-    use crate::day07::{part1, part2};
+fn part1(input: &str) -> Result<u64> {
+    let fs = load(input)?;
+    Ok(fs
+        .du()
+        .map(|(_, size)| size)
+        .filter(|&size| size <= 100000)
+        .sum())
+}
 
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 95437)
-    }
+const TOTAL_DISK_SPACE: u64 = 70000000;
+const REQUIRED_DISK_SPACE: u64 = 30000000;
 
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 24933642)
-    }
+fn part2(input: &str) -> Result<u64> {
+    let fs = load(input)?;
+    let free_space = TOTAL_DISK_SPACE.saturating_sub(fs.size(ROOT));
+    let needed_space = REQUIRED_DISK_SPACE.saturating_sub(free_space);
+    // Deleting the root directory always frees up enough space
+    Ok(fs
+        .du()
+        .map(|(_, size)| size)
+        .filter(|&size| size >= needed_space)
+        .min()
+        .unwrap())
+}
 
-    const SAMPLE_INPUT: &str = "$ cd /
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "$ cd /
 $ ls
 dir a
 14848514 b.txt
@@ -198,4 +397,130 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k";
+
+#[cfg(test)]
+mod test {
+    use crate::day07::{parse_input, PUZZLE, ROOT, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 95437;
+        part2_test: 2, SAMPLE_INPUT => 24933642;
+    }
+
+    #[test]
+    fn tree_test() {
+        let (fs, diagnostics) = parse_input(SAMPLE_INPUT).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(fs.to_string(), SAMPLE_TREE);
+    }
+
+    #[test]
+    fn query_test() {
+        let (fs, diagnostics) = parse_input(SAMPLE_INPUT).unwrap();
+        assert!(diagnostics.is_empty());
+        let du: Vec<(String, u64)> = fs.du().collect();
+        assert_eq!(
+            du,
+            vec![
+                ("/".to_string(), 48381165),
+                ("/a".to_string(), 94853),
+                ("/a/e".to_string(), 584),
+                ("/d".to_string(), 24933642),
+            ]
+        );
+        let logs: Vec<String> = fs
+            .find(|id| fs.node(id).name.ends_with(".log"))
+            .map(|id| fs.path(id))
+            .collect();
+        assert_eq!(logs, vec!["/d/d.log"]);
+        assert_eq!(fs.size(ROOT), 48381165);
+    }
+
+    #[test]
+    fn names_test() {
+        let (fs, _) =
+            parse_input("$ cd /\n$ cd v1.2\n$ ls\n12 a b.txt\ndir 3d\n$ cd 3d\n").unwrap();
+        let paths: Vec<String> = fs.find(|_| true).map(|id| fs.path(id)).collect();
+        assert_eq!(paths, vec!["/", "/v1.2", "/v1.2/3d", "/v1.2/a b.txt"]);
+        assert!(parse_input("$ cd /\n$ ls\n1 a/b\n").is_err());
+        assert!(parse_input("\n\n").is_err());
+    }
+
+    #[test]
+    fn unusual_session_test() {
+        let transcript = "$ ls
+dir a
+10 x
+$ cd /a/b/../c
+$ ls
+5 y
+$ cd ../..
+$ ls
+dir a
+10 x
+$ cd /a/c
+$ ls
+5 y
+
+";
+        let (fs, diagnostics) = parse_input(transcript).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let du: Vec<(String, u64)> = fs.du().collect();
+        assert_eq!(
+            du,
+            vec![
+                ("/".to_string(), 15),
+                ("/a".to_string(), 5),
+                ("/a/b".to_string(), 0),
+                ("/a/c".to_string(), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let transcript = "$ cd ..
+$ ls
+10 x
+dir d
+$ ls
+20 x
+1 d
+$ cd e
+$ cd /x
+3 z";
+        let (fs, diagnostics) = parse_input(transcript).unwrap();
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![1, 6, 7, 8, 9, 10]);
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "line 6: /x was 10 bytes, and is now 20"
+        );
+        assert_eq!(fs.size(ROOT), 23);
+    }
+
+    #[test]
+    fn too_big_test() {
+        let transcript = format!("$ cd /\n$ ls\n{} x\n1 y", u64::MAX);
+        let error = parse_input(&transcript).err().unwrap();
+        assert_eq!(error.to_string(), "/ is too big to add up");
+    }
+
+    const SAMPLE_TREE: &str = "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+";
 }
@@ -1,81 +1,93 @@
+use crate::parallel::par_max;
 use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use anyhow::{anyhow, bail, Result};
 use std::cmp::max;
+use tracing::{trace, trace_span};
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 8,
+    title: Some("Treetop Tree House"),
     part1: I32(part1),
     part2: I32(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
 type Matrix<T> = Vec<Vec<T>>;
 
-fn parse_matrix(input: &str) -> (Matrix<u32>, usize, usize) {
+pub(crate) fn parse_matrix(input: &str) -> Result<(Matrix<u32>, usize, usize)> {
     let matrix: Matrix<u32> = input
         .lines()
-        .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect();
+        .map(|line| {
+            line.chars()
+                .map(|c| c.to_digit(10).ok_or(anyhow!("Unexpected tree {}", c)))
+                .collect()
+        })
+        .collect::<Result<_>>()?;
     let h = matrix.len();
-    let w = matrix[0].len();
-    (matrix, w, h)
+    let w = matrix.first().map_or(0, |row| row.len());
+    if w == 0 {
+        bail!("No trees found");
+    }
+    if matrix.iter().any(|row| row.len() != w) {
+        bail!("Rows aren't all the same length");
+    }
+    Ok((matrix, w, h))
 }
 
-fn part1(input: &str) -> i32 {
-    let (trees, w, h) = parse_matrix(input);
+fn part1(input: &str) -> Result<i32> {
+    let (trees, w, h) = parse_matrix(input)?;
 
-    // define mutable matrix for storing visibility
-    let mut visible: Matrix<i32> = vec![vec![0; h]; w];
+    // define mutable matrix for storing visibility, with the same shape as the trees
+    let mut visible: Matrix<i32> = vec![vec![0; w]; h];
     // mark all edges as visible
-    for x in 0..w {
-        visible[x][0] = 1;
-        visible[x][h - 1] = 1;
-    }
-    for y in 0..h {
-        visible[0][y] = 1;
-        visible[w - 1][y] = 1;
+    for row in visible.iter_mut() {
+        row[0] = 1;
+        row[w - 1] = 1;
     }
+    visible[0].fill(1);
+    visible[h - 1].fill(1);
 
-    let mut find_visible = |x_range: Vec<usize>, y_range: Vec<usize>, rotate: bool| {
+    let mut find_visible = |from: &str, x_range: Vec<usize>, y_range: Vec<usize>, rotate: bool| {
+        let _span = trace_span!("find_visible", from).entered();
         for i in x_range {
             let mut max_height = 0;
             for j in y_range.clone() {
                 let (x, y) = if !rotate { (i, j) } else { (j, i) };
                 let tree = trees[x][y];
-                // print!("({},{}):{}", x, y, tree);
                 if tree > max_height {
-                    // print!("Visible!");
+                    trace!(x, y, tree, "Visible");
                     visible[x][y] = 1;
                 }
                 max_height = max(max_height, tree);
                 if max_height == 9 {
                     break;
                 }
-                // println!();
             }
         }
     };
 
-    //println!("left");
-    find_visible((1..h - 1).collect(), (0..w - 1).collect(), false);
-    //println!("top");
-    find_visible((1..w - 1).collect(), (0..h - 1).collect(), true);
-    //println!("right");
-    find_visible((1..h - 1).collect(), (1..w).rev().collect(), false);
-    //println!("bottom");
-    find_visible((1..w - 1).collect(), (1..h).rev().collect(), true);
+    find_visible("left", (1..h - 1).collect(), (0..w - 1).collect(), false);
+    find_visible("top", (1..w - 1).collect(), (0..h - 1).collect(), true);
+    find_visible("right", (1..h - 1).collect(), (1..w).rev().collect(), false);
+    find_visible("bottom", (1..w - 1).collect(), (1..h).rev().collect(), true);
 
     // sum up the visible trees
-    visible.iter().map(|row| row.iter().sum::<i32>()).sum()
+    Ok(visible.iter().map(|row| row.iter().sum::<i32>()).sum())
 }
 
-fn part2(input: &str) -> i32 {
-    let (trees, w, h) = parse_matrix(input);
-
-    let points = (0..h).flat_map(|x| (0..w).map(move |y| (x, y)));
-
-    points
-        .map(|tree| scenic_score(tree, &trees, w, h))
-        .max()
-        .unwrap()
+fn part2(input: &str) -> Result<i32> {
+    let (trees, w, h) = parse_matrix(input)?;
+
+    // Each row is scored independently. parse_matrix makes sure there is at least one tree.
+    Ok(par_max(0..h, |x| {
+        (0..w)
+            .map(|y| scenic_score((x, y), &trees, w, h))
+            .max()
+            .unwrap()
+    })
+    .unwrap())
 }
 
 fn scenic_score(tree: (usize, usize), trees: &Matrix<u32>, w: usize, h: usize) -> i32 {
@@ -84,9 +96,9 @@ fn scenic_score(tree: (usize, usize), trees: &Matrix<u32>, w: usize, h: usize) -
     let height = trees[x][y];
 
     let mut up = 0;
-    for xx in (0..x).rev() {
+    for row in trees[..x].iter().rev() {
         up += 1;
-        if trees[xx][y] >= height {
+        if row[y] >= height {
             break;
         }
     }
@@ -100,52 +112,48 @@ fn scenic_score(tree: (usize, usize), trees: &Matrix<u32>, w: usize, h: usize) -
     }
 
     let mut right = 0;
-    for yy in y + 1..w {
+    for &i in &trees[x][y + 1..w] {
         right += 1;
-        let i = trees[x][yy];
         if i >= height {
             break;
         }
     }
 
     let mut down = 0;
-    for xx in x + 1..h {
+    for row in &trees[x + 1..h] {
         down += 1;
-        if trees[xx][y] >= height {
+        if row[y] >= height {
             break;
         }
     }
 
-    // println!("{} * {} * {} * {}", up, left, right, down);
+    trace!(x, y, up, left, right, down, "Scenic score");
     up * left * right * down
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "30373
+25512
+65332
+33549
+35390";
+
 #[cfg(test)]
 mod test {
-    use crate::day08::{parse_matrix, part1, part2, scenic_score};
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 21)
+    use crate::day08::{parse_matrix, scenic_score, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 21;
+        part2_test: 2, SAMPLE_INPUT => 8;
+        rectangle_test: 1, "3037\n2551\n6533" => 12;
     }
 
     #[test]
     fn scenic_score_test() {
-        let (trees, w, h) = parse_matrix(SAMPLE_INPUT);
+        let (trees, w, h) = parse_matrix(SAMPLE_INPUT).unwrap();
         let score = scenic_score((1, 2), &trees, w, h);
         assert_eq!(score, 4)
     }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 8)
-    }
-
-    const SAMPLE_INPUT: &str = "30373
-25512
-65332
-33549
-35390";
 }
@@ -1,16 +1,22 @@
 use crate::day09::Direction::{DOWN, LEFT, RIGHT, UP};
 use crate::puzzle::{Puzzle, PuzzleFn::USIZE};
+use anyhow::{anyhow, bail, Result};
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
+use tracing::trace;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 9,
+    title: Some("Rope Bridge"),
     part1: USIZE(part1),
     part2: USIZE(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
 #[derive(Debug, Clone, Copy)]
-enum Direction {
+pub(crate) enum Direction {
     UP,
     DOWN,
     LEFT,
@@ -28,32 +34,34 @@ impl Display for Direction {
     }
 }
 
-struct Instruction {
+pub(crate) struct Instruction {
     direction: Direction,
     n: i32,
 }
 
-fn parse_line(line: &str) -> Instruction {
-    let mut parts = line.split(' ');
-    let direction = match parts.next() {
-        Some("U") => UP,
-        Some("D") => DOWN,
-        Some("R") => RIGHT,
-        Some("L") => LEFT,
-        _ => panic!("Can't parse {}", line),
+fn parse_line(line: &str) -> Result<Instruction> {
+    let (direction, n) = line
+        .split_once(' ')
+        .ok_or(anyhow!("Can't parse {}", line))?;
+    let direction = match direction {
+        "U" => UP,
+        "D" => DOWN,
+        "R" => RIGHT,
+        "L" => LEFT,
+        _ => bail!("Can't parse {}", line),
     };
-    let n: i32 = parts.next().unwrap().parse().unwrap();
-    Instruction { direction, n }
+    let n: i32 = n.parse()?;
+    Ok(Instruction { direction, n })
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Instruction>> {
     input.lines().map(parse_line).collect()
 }
 
 type Point = (i32, i32);
 
-fn part1(input: &str) -> usize {
-    let instructions = parse_input(input);
+fn part1(input: &str) -> Result<usize> {
+    let instructions = parse_input(input)?;
 
     let mut head: Point = (0, 0);
     let mut tail: Point = (0, 0);
@@ -66,13 +74,10 @@ fn part1(input: &str) -> usize {
             tail = follow(head, tail);
             tail_positions.insert(tail);
         }
-        // println!(
-        //     "{} {} ({},{}) ({},{})",
-        //     instruction.direction, instruction.n, head.0, head.1, tail.0, tail.1
-        // );
+        trace!(direction = %instruction.direction, n = instruction.n, ?head, ?tail);
     }
 
-    tail_positions.len()
+    Ok(tail_positions.len())
 }
 
 fn move_point(p: Point, direction: &Direction) -> Point {
@@ -95,8 +100,8 @@ fn follow(head: Point, tail: Point) -> Point {
     }
 }
 
-fn part2(input: &str) -> usize {
-    let instructions = parse_input(input);
+fn part2(input: &str) -> Result<usize> {
+    let instructions = parse_input(input)?;
 
     let mut knots: Vec<Point> = vec![(0, 0); 10];
     let mut tail_positions: HashSet<Point> = HashSet::new();
@@ -110,46 +115,89 @@ fn part2(input: &str) -> usize {
             }
             tail_positions.insert(knots[9]);
         }
-        // println!(
-        //     "{} {} ({},{}) ({},{})",
-        //     instruction.direction, instruction.n, head.0, head.1, tail.0, tail.1
-        // );
+        trace!(direction = %instruction.direction, n = instruction.n, knots = ?knots);
     }
 
-    tail_positions.len()
+    Ok(tail_positions.len())
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2";
+
 #[cfg(test)]
 mod test {
-    use crate::day09::{part1, part2, PUZZLE};
+    use crate::day09::{part1, part2, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::{differential, puzzle_tests};
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 13;
+        part2_test: 2, SAMPLE_INPUT => 1;
+        part2_larger_test: 2, SAMPLE_INPUT_2 => 36;
+    }
 
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 13)
+    fn rope_moves() -> impl Strategy<Value = String> {
+        let direction = prop::sample::select(vec!['U', 'D', 'L', 'R']);
+        vec((direction, 1..20), 0..100)
+            .prop_map(|moves| moves.iter().map(|(d, n)| format!("{} {}", d, n)).join("\n"))
+    }
+
+    // Move the head one step at a time, and after each step move every knot that no longer
+    // touches the one ahead of it one step closer
+    fn simulate(input: &str, knots: usize) -> usize {
+        let mut rope = vec![(0i32, 0i32); knots];
+        let mut visited = HashSet::from([(0, 0)]);
+        for line in input.lines() {
+            let (direction, n) = line.split_once(' ').unwrap();
+            let step = match direction {
+                "U" => (1, 0),
+                "D" => (-1, 0),
+                "L" => (0, -1),
+                _ => (0, 1),
+            };
+            for _ in 0..n.parse().unwrap() {
+                rope[0] = (rope[0].0 + step.0, rope[0].1 + step.1);
+                for i in 1..knots {
+                    let (dx, dy) = (rope[i - 1].0 - rope[i].0, rope[i - 1].1 - rope[i].1);
+                    if dx.abs().max(dy.abs()) > 1 {
+                        rope[i] = (rope[i].0 + dx.clamp(-1, 1), rope[i].1 + dy.clamp(-1, 1));
+                    }
+                }
+                visited.insert(rope[knots - 1]);
+            }
+        }
+        visited.len()
     }
 
     #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 1);
-        let answer = part2(SAMPLE_INPUT_2);
-        assert_eq!(answer, 36);
+    fn part1_differential() {
+        differential(
+            rope_moves(),
+            |input| simulate(input, 2),
+            |input| part1(input).unwrap(),
+        );
     }
 
     #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
+    fn part2_differential() {
+        differential(
+            rope_moves(),
+            |input| simulate(input, 10),
+            |input| part2(input).unwrap(),
+        );
     }
 
-    const SAMPLE_INPUT: &str = "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2";
     const SAMPLE_INPUT_2: &str = "R 5
 U 8
 L 8
@@ -1,24 +1,34 @@
 use crate::day10::Instruction::{ADDX, NOOP};
-use crate::puzzle::PuzzleFn::STR;
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::puzzle::{Puzzle, PuzzleFn::STREAM};
+use crate::util::{parse_all, LineReader};
+use anyhow::{bail, Result};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::sequence::preceded;
 use nom::IResult;
+use std::io::{BufRead, Write};
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 10,
-    part1: I32(part1),
-    part2: STR(part2),
+    title: Some("Cathode-Ray Tube"),
+    part1: STREAM(|input| Ok(part1(input)?.to_string())),
+    part2: STREAM(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-enum Instruction {
+pub(crate) enum Instruction {
     NOOP,
     ADDX(i32),
 }
 
-fn parse_instruction(line: &str) -> Instruction {
-    alt((noop, addx))(line).unwrap().1
+fn parse_instruction(line: &str) -> Result<Instruction> {
+    parse_all(alt((noop, addx)), line)
+}
+
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Instruction>> {
+    input.lines().map(parse_instruction).collect()
 }
 
 fn noop(i: &str) -> IResult<&str, Instruction> {
@@ -31,12 +41,18 @@ fn addx(i: &str) -> IResult<&str, Instruction> {
     Ok((input, ADDX(v)))
 }
 
-fn execute(instructions: Vec<Instruction>) -> Vec<i32> {
+// The value of X during each cycle, starting with the first. The program is only read until the
+// first `cycles` cycles are known.
+fn execute(input: &mut dyn BufRead, cycles: usize) -> Result<Vec<i32>> {
     let mut x = 1;
     let mut x_hist: Vec<i32> = vec![1];
 
-    for instr in instructions {
-        match instr {
+    let mut lines = LineReader::new(input);
+    while x_hist.len() < cycles {
+        let Some(line) = lines.next_line()? else {
+            break;
+        };
+        match parse_instruction(line)? {
             NOOP => x_hist.push(x),
             ADDX(v) => {
                 x_hist.push(x);
@@ -46,20 +62,37 @@ fn execute(instructions: Vec<Instruction>) -> Vec<i32> {
         }
     }
 
-    x_hist
+    Ok(x_hist)
 }
 
-fn part1(input: &str) -> i32 {
-    let instructions = input.lines().map(parse_instruction);
-    let x_hist = execute(instructions.collect());
+// Like `execute`, but it's an error if the program doesn't run for `cycles` cycles
+fn execute_at_least(input: &mut dyn BufRead, cycles: usize) -> Result<Vec<i32>> {
+    let x_hist = execute(input, cycles)?;
+    if x_hist.len() < cycles {
+        bail!(
+            "The program ended after {} cycles, but {} are needed",
+            x_hist.len(),
+            cycles
+        );
+    }
+    Ok(x_hist)
+}
+
+fn part1(input: &mut dyn BufRead) -> Result<i32> {
+    let x_hist = execute_at_least(input, 220)?;
     let signal_strength = |cycle: i32| cycle * x_hist[cycle as usize - 1];
-    (0..6).map(|i| signal_strength(i * 40 + 20)).sum()
+    Ok((0..6).map(|i| signal_strength(i * 40 + 20)).sum())
+}
+
+fn part2(input: &mut dyn BufRead) -> Result<String> {
+    let x_hist = execute_at_least(input, 240)?;
+    let mut screen = vec![b'\n'];
+    render_screen(&mut screen, &x_hist)?;
+    Ok(String::from_utf8(screen)?)
 }
 
-fn part2(input: &str) -> String {
-    let instructions = input.lines().map(parse_instruction);
-    let x_hist = execute(instructions.collect());
-    let mut output: Vec<char> = vec!['\n'];
+// Draw the six rows of the CRT, lighting each pixel that the sprite covers as it is drawn
+fn render_screen(out: &mut dyn Write, x_hist: &[i32]) -> std::io::Result<()> {
     for row in 0..6 {
         for p in 0..40 {
             let x = x_hist[(row * 40 + p) as usize];
@@ -68,52 +101,15 @@ fn part2(input: &str) -> String {
             } else {
                 '.'
             };
-            output.push(c);
+            write!(out, "{}", c)?;
         }
-        output.push('\n');
+        writeln!(out)?;
     }
-    output.into_iter().collect()
+    Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use crate::day10::{execute, parse_instruction, part1, part2, PUZZLE};
-
-    #[test]
-    fn small_example() {
-        let program = "noop
-addx 3
-addx -5";
-        let x_hist = execute(program.lines().map(parse_instruction).collect());
-        assert_eq!(x_hist, vec![1, 1, 1, 4, 4, -1])
-    }
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 13140)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        let expected = "
-##..##..##..##..##..##..##..##..##..##..
-###...###...###...###...###...###...###.
-####....####....####....####....####....
-#####.....#####.....#####.....#####.....
-######......######......######......####
-#######.......#######.......#######.....
-";
-        assert_eq!(answer, expected);
-    }
-
-    #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
-    }
-
-    const SAMPLE_INPUT: &str = "addx 15
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "addx 15
 addx -11
 addx 6
 addx -3
@@ -259,4 +255,48 @@ addx -11
 noop
 noop
 noop";
+
+#[cfg(test)]
+mod test {
+    use crate::day10::{execute, execute_at_least, render_screen, PUZZLE, SAMPLE_INPUT};
+    use crate::snapshot::{check_snapshot, Colour};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 13140;
+        part2_test: 2, SAMPLE_INPUT => SAMPLE_SCREEN;
+    }
+
+    #[test]
+    fn small_example() {
+        let program = "noop
+addx 3
+addx -5";
+        let x_hist = execute(&mut program.as_bytes(), usize::MAX).unwrap();
+        assert_eq!(x_hist, vec![1, 1, 1, 4, 4, -1]);
+        let x_hist = execute(&mut program.as_bytes(), 2).unwrap();
+        assert_eq!(x_hist, vec![1, 1])
+    }
+
+    #[test]
+    fn screen_snapshot() {
+        let x_hist = execute_at_least(&mut SAMPLE_INPUT.as_bytes(), 240).unwrap();
+        let mut screen = vec![];
+        render_screen(&mut screen, &x_hist).unwrap();
+        check_snapshot(
+            "day10_screen",
+            &String::from_utf8(screen).unwrap(),
+            Colour::Strip,
+        );
+    }
+
+    const SAMPLE_SCREEN: &str = "
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....
+";
 }
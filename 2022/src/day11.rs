@@ -1,4 +1,6 @@
-use crate::puzzle::{Puzzle, PuzzleFn::U64};
+use crate::puzzle::{ParsedFns, Puzzle};
+use crate::util::parse_all;
+use anyhow::{bail, Context, Result};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::u32 as nom_u32;
@@ -9,12 +11,20 @@ use nom::sequence::{delimited, preceded, tuple};
 use nom::IResult;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 11,
-    part1: U64(part1),
-    part2: U64(part2),
+    title: Some("Monkey in the Middle"),
+    parsed: Some(&ParsedFns {
+        parse: parse_input,
+        part1,
+        part2,
+    }),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-struct Monkey {
+#[derive(Clone)]
+pub(crate) struct Monkey {
     _n: u32,
     items: Vec<u64>,
     operation: Operation,
@@ -25,21 +35,23 @@ struct Monkey {
 }
 
 impl Monkey {
-    fn inspect(&self, item: u64) -> u64 {
+    // Without relief, or with a big enough modulus, worry levels soon get too big to hold
+    fn inspect(&self, item: u64) -> Result<u64> {
         let op = &self.operation;
         let eval = |operand: &Operand| match operand {
-            Operand::VAR(_) => item,
+            Operand::VAR => item,
             Operand::INT(v) => *v,
         };
         let (a, b) = (eval(&op.a), eval(&op.b));
         match op.op {
-            Operator::MULT => a * b,
-            Operator::ADD => a + b,
+            Operator::MULT => a.checked_mul(b),
+            Operator::ADD => a.checked_add(b),
         }
+        .with_context(|| format!("Worry level {} got too big", item))
     }
 
     fn throw(&self, item: u64) -> usize {
-        if item % self.divisible_by == 0 {
+        if item.is_multiple_of(self.divisible_by) {
             self.if_true as usize
         } else {
             self.if_false as usize
@@ -51,43 +63,55 @@ impl Monkey {
         items: &Vec<u64>,
         divisor: Option<u64>,
         modulus: Option<u64>,
-    ) -> Vec<(usize, u64)> {
+    ) -> Result<Vec<(usize, u64)>> {
         let mut thrown = vec![];
         for item in items {
-            let worry = self.inspect(*item) / divisor.unwrap_or(1);
-            let worry = if modulus.is_some() {
-                worry % modulus.unwrap()
-            } else {
-                worry
+            let worry = self.inspect(*item)? / divisor.unwrap_or(1);
+            let worry = match modulus {
+                Some(modulus) => worry % modulus,
+                None => worry,
             };
             let throw_to = self.throw(worry);
             thrown.push((throw_to, worry))
         }
-        thrown
+        Ok(thrown)
     }
 }
 
+#[derive(Clone)]
 struct Operation {
     a: Operand,
     op: Operator,
     b: Operand,
 }
 
+#[derive(Clone)]
 enum Operator {
     MULT,
     ADD,
 }
 
+#[derive(Clone)]
 enum Operand {
-    VAR(String),
+    VAR,
     INT(u64),
 }
 
-fn parse_input(input: &str) -> Vec<Monkey> {
-    input
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Monkey>> {
+    let monkeys: Vec<Monkey> = input
+        .trim_end()
         .split("\n\n")
-        .map(|line| monkey(line).unwrap().1)
-        .collect()
+        .map(|block| parse_all(monkey, block.trim_end()))
+        .collect::<Result<_>>()?;
+    for (i, m) in monkeys.iter().enumerate() {
+        if m.divisible_by == 0 {
+            bail!("Monkey {} tests for divisibility by 0", i);
+        }
+        if m.if_true as usize >= monkeys.len() || m.if_false as usize >= monkeys.len() {
+            bail!("Monkey {} throws to a monkey that doesn't exist", i);
+        }
+    }
+    Ok(monkeys)
 }
 
 // Monkey Parser
@@ -106,7 +130,7 @@ fn monkey(s: &str) -> IResult<&str, Monkey> {
         alt((add, mult))(i)
     }
     fn operand(i: &str) -> IResult<&str, Operand> {
-        let var = nom_map(tag("old"), |v: &str| Operand::VAR(v.to_string()));
+        let var = nom_map(tag("old"), |_| Operand::VAR);
         let int = nom_map(nom_u64, Operand::INT);
         alt((var, int))(i)
     }
@@ -141,12 +165,12 @@ fn monkey_business(
     rounds: u32,
     divisor: Option<u64>,
     modulus: Option<u64>,
-) -> u64 {
+) -> Result<u64> {
     for _ in 0..rounds {
         for i in 0..monkeys.len() {
             let monkey = monkeys.get_mut(i).unwrap();
             monkey.inspections += monkey.items.len() as u64;
-            let thrown = monkey.take_turn(&monkey.items, divisor, modulus);
+            let thrown = monkey.take_turn(&monkey.items, divisor, modulus)?;
             monkey.items.clear();
             for (m, i) in thrown {
                 monkeys.get_mut(m).unwrap().items.push(i);
@@ -156,71 +180,27 @@ fn monkey_business(
 
     let mut inspection_count: Vec<u64> = monkeys.iter().map(|m| m.inspections).collect();
     inspection_count.sort();
-    inspection_count.iter().rev().take(2).product()
+    Ok(inspection_count.iter().rev().take(2).product())
 }
 
-fn part1(input: &str) -> u64 {
-    let monkeys = parse_input(input);
-    monkey_business(monkeys, 20, Some(3), None)
+// Both parts play with their own copy of the monkeys. They take a &Vec since that is what the
+// parse stage produces.
+#[allow(clippy::ptr_arg)]
+fn part1(monkeys: &Vec<Monkey>) -> Result<u64> {
+    monkey_business(monkeys.clone(), 20, Some(3), None)
 }
 
-fn part2(input: &str) -> u64 {
-    let monkeys = parse_input(input);
-    let modulus = monkeys.iter().map(|m| m.divisible_by).product();
-    monkey_business(monkeys, 10000, None, Some(modulus))
+#[allow(clippy::ptr_arg)]
+fn part2(monkeys: &Vec<Monkey>) -> Result<u64> {
+    let modulus = monkeys
+        .iter()
+        .try_fold(1u64, |product, m| product.checked_mul(m.divisible_by))
+        .context("The monkeys' tests have too big a product")?;
+    monkey_business(monkeys.clone(), 10000, None, Some(modulus))
 }
 
-#[cfg(test)]
-mod test {
-    use crate::day11::{parse_input, part1, part2, PUZZLE};
-
-    #[test]
-    fn parser_test() {
-        let monkeys = parse_input(SAMPLE_INPUT);
-        assert_eq!(monkeys.len(), 4);
-        assert_eq!(monkeys[0]._n, 0);
-        assert_eq!(monkeys[0].items, vec![79, 98]);
-    }
-
-    #[test]
-    fn monkey_inspection_test() {
-        let monkey = &parse_input(SAMPLE_INPUT)[0];
-        let worry = monkey.inspect(monkey.items[0]);
-        assert_eq!(worry, 1501);
-    }
-
-    #[test]
-    fn monkey_throw_test() {
-        let monkey = &parse_input(SAMPLE_INPUT)[0];
-        let throw_to = monkey.throw(500);
-        assert_eq!(throw_to, 3);
-    }
-
-    #[test]
-    fn monkey_take_turn_test() {
-        let monkey = &parse_input(SAMPLE_INPUT)[0];
-        let thrown = monkey.take_turn(&monkey.items, Some(3), None);
-        assert_eq!(thrown, vec![(3, 500), (3, 620)]);
-    }
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 10605)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 2713310158)
-    }
-
-    #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
-    }
-
-    const SAMPLE_INPUT: &str = "Monkey 0:
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "Monkey 0:
   Starting items: 79, 98
   Operation: new = old * 19
   Test: divisible by 23
@@ -248,4 +228,45 @@ Monkey 3:
     If true: throw to monkey 0
     If false: throw to monkey 1
 ";
+
+#[cfg(test)]
+mod test {
+    use crate::day11::{parse_input, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 10605;
+        part2_test: 2, SAMPLE_INPUT => 2713310158u64;
+    }
+
+    #[test]
+    fn parser_test() {
+        let monkeys = parse_input(SAMPLE_INPUT).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[0]._n, 0);
+        assert_eq!(monkeys[0].items, vec![79, 98]);
+    }
+
+    #[test]
+    fn monkey_inspection_test() {
+        let monkey = &parse_input(SAMPLE_INPUT).unwrap()[0];
+        let worry = monkey.inspect(monkey.items[0]).unwrap();
+        assert_eq!(worry, 1501);
+        assert!(monkey.inspect(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn monkey_throw_test() {
+        let monkey = &parse_input(SAMPLE_INPUT).unwrap()[0];
+        let throw_to = monkey.throw(500);
+        assert_eq!(throw_to, 3);
+    }
+
+    #[test]
+    fn monkey_take_turn_test() {
+        let monkey = &parse_input(SAMPLE_INPUT).unwrap()[0];
+        let thrown = monkey.take_turn(&monkey.items, Some(3), None).unwrap();
+        assert_eq!(thrown, vec![(3, 500), (3, 620)]);
+    }
 }
@@ -1,22 +1,32 @@
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::puzzle::{ParsedFns, Puzzle};
 use crate::util::color_gradient;
+use anyhow::{bail, Result};
 use itertools::Itertools;
 use std::collections::{HashSet, VecDeque};
+use std::io::{self, Write};
+use tracing::{debug, enabled, Level};
 use yansi::Paint;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 12,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Hill Climbing Algorithm"),
+    parsed: Some(&ParsedFns {
+        parse: parse_input,
+        part1,
+        part2,
+    }),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-struct TrailMap {
+pub(crate) struct TrailMap {
     topo: Matrix<char>,
     start: Point,
     end: Point,
 }
 
-struct Matrix<T> {
+pub(crate) struct Matrix<T> {
     inner: Vec<Vec<T>>,
     m: i32,
     n: i32,
@@ -38,7 +48,7 @@ impl<T> Matrix<T> {
     }
 
     fn neighbors(&self, (i, j): &Point) -> Vec<Point> {
-        vec![(i + 1, *j), (i - 1, *j), (*i, j + 1), (*i, j - 1)]
+        [(i + 1, *j), (i - 1, *j), (*i, j + 1), (*i, j - 1)]
             .iter()
             .filter(|p| self.get(p).is_some())
             .copied()
@@ -46,8 +56,15 @@ impl<T> Matrix<T> {
     }
 }
 
-fn print_map(map: &Matrix<char>, dist: &Matrix<i32>, goal: &Point) {
-    let goal_distance = dist.get(&goal).unwrap();
+// Draw the map, coloured by distance from the start, with the path to the goal highlighted
+fn render_map(
+    out: &mut dyn Write,
+    map: &Matrix<char>,
+    dist: &Matrix<i32>,
+    goal: &Point,
+) -> io::Result<()> {
+    // A goal at the start would leave no distance to spread the colours over
+    let goal_distance = (*dist.get(goal).unwrap()).max(1);
     let path: HashSet<Point> = HashSet::from_iter(get_path(map, dist, goal));
     for i in 0..map.m {
         for j in 0..map.n {
@@ -63,15 +80,15 @@ fn print_map(map: &Matrix<char>, dist: &Matrix<i32>, goal: &Point) {
                 };
                 color_gradient(d * (360 / goal_distance), lightness)
             };
-            print!("{} ", Paint::rgb(r, g, b, c));
+            write!(out, "{} ", Paint::rgb(r, g, b, c))?;
         }
-        println!();
+        writeln!(out)?;
     }
-    println!();
+    writeln!(out)
 }
 
 fn get_path(map: &Matrix<char>, dist: &Matrix<i32>, goal: &Point) -> Vec<Point> {
-    let mut p = goal.clone();
+    let mut p = *goal;
     let mut path: Vec<Point> = vec![p];
     loop {
         let neighbors = map.neighbors(&p);
@@ -90,23 +107,32 @@ fn get_path(map: &Matrix<char>, dist: &Matrix<i32>, goal: &Point) -> Vec<Point>
     path
 }
 
-fn parse_input(input: &str) -> TrailMap {
+pub(crate) fn parse_input(input: &str) -> Result<TrailMap> {
     let inner: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
     let m = inner.len() as i32;
-    let n = inner[0].len() as i32;
-    let mut start: Point = (0, 0);
-    let mut end: Point = (0, 0);
+    let n = inner.first().map_or(0, |row| row.len()) as i32;
+    if inner.iter().any(|row| row.len() != n as usize) {
+        bail!("Rows aren't all the same length");
+    }
+    let (mut start, mut end): (Option<Point>, Option<Point>) = (None, None);
     for i in 0..m {
         for j in 0..n {
-            match inner[i as usize][j as usize] {
-                'S' => start = (i, j),
-                'E' => end = (i, j),
-                _ => {}
+            let marker = match inner[i as usize][j as usize] {
+                'S' => &mut start,
+                'E' => &mut end,
+                'a'..='z' => continue,
+                c => bail!("Unexpected {} in the map", c),
+            };
+            if marker.replace((i, j)).is_some() {
+                bail!("The map has more than one start or end");
             }
         }
     }
+    let (Some(start), Some(end)) = (start, end) else {
+        bail!("The map needs a start and an end");
+    };
     let topo = Matrix { inner, m, n };
-    TrailMap { topo, start, end }
+    Ok(TrailMap { topo, start, end })
 }
 
 fn val(c: &char) -> i32 {
@@ -116,12 +142,36 @@ fn val(c: &char) -> i32 {
         _ => c.to_digit(36).unwrap() as i32 - 10,
     }
 }
+
 fn shortest_path(
     topo: &Matrix<char>,
     start: Point,
     path_ok: fn(i32, i32) -> bool,
     is_goal: fn(char) -> bool,
-) -> i32 {
+) -> Result<i32> {
+    let search = search(topo, start, path_ok, is_goal)?;
+    // Drawing the map takes a while, so it is only done when it will be shown
+    if enabled!(Level::DEBUG) {
+        let mut render = vec![];
+        render_map(&mut render, topo, &search.dist, &search.goal).unwrap();
+        debug!("The map\n{}", String::from_utf8_lossy(&render));
+    }
+    Ok(search.steps)
+}
+
+// How far the search got from the start to each point, and to the goal
+struct Search {
+    dist: Matrix<i32>,
+    goal: Point,
+    steps: i32,
+}
+
+fn search(
+    topo: &Matrix<char>,
+    start: Point,
+    path_ok: fn(i32, i32) -> bool,
+    is_goal: fn(char) -> bool,
+) -> Result<Search> {
     let mut dist: Matrix<i32> = Matrix {
         inner: vec![vec![i32::MAX; topo.n as usize]; topo.m as usize],
         m: topo.m,
@@ -130,7 +180,7 @@ fn shortest_path(
 
     dist.set(&start, 0);
     let mut curr_dist = 0;
-    let mut goal = (0, 0);
+    let mut goal = None;
     let mut q: VecDeque<Point> = VecDeque::from([start]);
     'outer: loop {
         if q.is_empty() {
@@ -150,7 +200,7 @@ fn shortest_path(
                 if path_ok(p_elevation, elevation) {
                     dist.set(&neighbor, curr_dist);
                     if is_goal(*c) {
-                        goal = neighbor;
+                        goal = Some(neighbor);
                         break 'outer;
                     }
                     q.push_back(neighbor);
@@ -158,29 +208,44 @@ fn shortest_path(
             }
         }
     }
-    print_map(&topo, &dist, &goal);
-    curr_dist
+    let Some(goal) = goal else {
+        bail!("No path to the goal");
+    };
+    Ok(Search {
+        dist,
+        goal,
+        steps: curr_dist,
+    })
 }
 
-#[allow(unused_variables)]
-fn part1(input: &str) -> i32 {
-    let map = parse_input(input);
-    let TrailMap { topo, start, end } = map;
+fn part1(map: &TrailMap) -> Result<i32> {
     let path_ok = |here, there| there - here <= 1;
-    shortest_path(&topo, start, path_ok, |c| c == 'E')
+    shortest_path(&map.topo, map.start, path_ok, |c| c == 'E')
 }
 
-#[allow(unused_variables)]
-fn part2(input: &str) -> i32 {
-    let map = parse_input(input);
-    let TrailMap { topo, start, end } = map;
+fn part2(map: &TrailMap) -> Result<i32> {
     let path_ok = |here, there| here - there <= 1;
-    shortest_path(&topo, end, path_ok, |c| c == 'a' || c == 'S')
+    shortest_path(&map.topo, map.end, path_ok, |c| c == 'a' || c == 'S')
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi";
+
 #[cfg(test)]
 mod test {
-    use crate::day12::{part1, part2, val, PUZZLE};
+    use crate::day12::{parse_input, part1, part2, render_map, search, val, PUZZLE, SAMPLE_INPUT};
+    use crate::snapshot::{check_snapshot, Colour};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 31;
+        part2_test: 2, SAMPLE_INPUT => 29;
+    }
 
     #[test]
     fn val_test() {
@@ -191,25 +256,30 @@ mod test {
     }
 
     #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 31)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 29)
+    fn no_path_test() {
+        let map = parse_input("SbE").unwrap();
+        assert!(part1(&map).is_err());
+        assert!(part2(&map).is_err());
     }
 
     #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
+    fn map_snapshot() {
+        let map = parse_input(SAMPLE_INPUT).unwrap();
+        let search = search(
+            &map.topo,
+            map.start,
+            |here, there| there - here <= 1,
+            |c| c == 'E',
+        )
+        .unwrap();
+        let mut render = vec![];
+        render_map(&mut render, &map.topo, &search.dist, &search.goal).unwrap();
+        check_snapshot(
+            "day12_map",
+            &String::from_utf8(render).unwrap(),
+            Colour::Keep,
+        );
+        // The start is no distance from itself
+        render_map(&mut vec![], &map.topo, &search.dist, &map.start).unwrap();
     }
-
-    const SAMPLE_INPUT: &str = "Sabqponm
-abcryxxl
-accszExk
-acctuvwj
-abdefghi";
 }
@@ -1,5 +1,7 @@
 use crate::day13::Value::List;
 use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::util::parse_all;
+use anyhow::{anyhow, bail, Result};
 use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
@@ -12,13 +14,18 @@ use std::cmp::Ordering;
 use Value::Int;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 13,
+    title: Some("Distress Signal"),
     part1: I32(part1),
     part2: I32(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
+/// A packet, ordered the way the distress signal wants them sorted
 #[derive(Clone, Debug, PartialEq, Eq)]
-enum Value {
+pub enum Value {
     Int(i32),
     List(Vec<Value>),
 }
@@ -40,15 +47,36 @@ impl Ord for Value {
     }
 }
 
-fn parse_input(input: &str) -> Vec<(Value, Value)> {
+/// The pairs of packets in the input
+pub fn parse_input(input: &str) -> Result<Vec<(Value, Value)>> {
     input.split("\n\n").map(parse_packets).collect()
 }
 
-fn parse_packets(i: &str) -> (Value, Value) {
-    let mut lines = i.lines();
-    let (_, left) = list(lines.next().unwrap()).unwrap();
-    let (_, right) = list(lines.next().unwrap()).unwrap();
-    (left, right)
+fn parse_packets(i: &str) -> Result<(Value, Value)> {
+    let lines: Vec<&str> = i.lines().collect();
+    let [left, right] = lines[..] else {
+        return Err(anyhow!("Expected a pair of packets but got {:?}", i));
+    };
+    Ok((parse_packet(left)?, parse_packet(right)?))
+}
+
+// Deeper lists than this would overflow the stack of the recursive parser
+const MAX_DEPTH: usize = 100;
+
+/// A single packet, like `[1,[2,[]]]`
+pub fn parse_packet(line: &str) -> Result<Value> {
+    let mut depth: usize = 0;
+    for c in line.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth > MAX_DEPTH {
+            bail!("Packet is nested more than {} lists deep", MAX_DEPTH);
+        }
+    }
+    parse_all(list, line)
 }
 
 fn int(i: &str) -> IResult<&str, Value> {
@@ -70,81 +98,40 @@ fn ordered_correctly((left, right): &(Value, Value)) -> bool {
     left < right
 }
 
-fn part1(input: &str) -> i32 {
-    let result: Vec<bool> = parse_input(input).iter().map(ordered_correctly).collect();
-    result
+fn part1(input: &str) -> Result<i32> {
+    let result: Vec<bool> = parse_input(input)?.iter().map(ordered_correctly).collect();
+    Ok(result
         .iter()
         .enumerate()
         .filter_map(|(i, v)| if *v { Some(i as i32 + 1) } else { None })
-        .sum()
+        .sum())
 }
 
 const DIVIDER_PACKETS: &str = "[[2]]\n[[6]]\n";
 
-fn part2(input: &str) -> i32 {
-    (DIVIDER_PACKETS.to_string() + input)
+fn part2(input: &str) -> Result<i32> {
+    let packets: Vec<Value> = (DIVIDER_PACKETS.to_string() + input)
         .lines()
-        .filter_map(|l| match list(l) {
-            Ok((_, v)) => Some(v),
-            Err(_) => None,
-        })
+        .filter(|l| !l.is_empty())
+        .map(parse_packet)
+        .collect::<Result<_>>()?;
+    let (d2, d6) = parse_packets(DIVIDER_PACKETS)?;
+    Ok(packets
+        .into_iter()
         .sorted()
         .enumerate()
         .filter_map(|(i, v)| {
-            let (d2, d6) = parse_packets(DIVIDER_PACKETS);
             if v == d2 || v == d6 {
                 Some(i as i32 + 1)
             } else {
                 None
             }
         })
-        .product()
+        .product())
 }
 
-#[cfg(test)]
-mod test {
-    use crate::day13::Value::{Int, List};
-    use crate::day13::{list, ordered_correctly, parse_packets, part1, part2, PUZZLE};
-
-    #[test]
-    fn parser_test() {
-        let (_, result) = list("[1,1,3,1,[[]]]").unwrap();
-        let expected = List(vec![
-            Int(1),
-            Int(1),
-            Int(3),
-            Int(1),
-            List(vec![List(vec![])]),
-        ]);
-        assert_eq!(result, expected)
-    }
-
-    #[test]
-    fn order_test() {
-        assert!(ordered_correctly(&parse_packets(
-            "[1,1,3,1,1]\n[1,1,5,1,1]"
-        )));
-        assert!(ordered_correctly(&parse_packets("[[1],[2,3,4]]\n[[1],4]")));
-    }
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 13)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 140)
-    }
-
-    #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
-    }
-
-    const SAMPLE_INPUT: &str = "[1,1,3,1,1]
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "[1,1,3,1,1]
 [1,1,5,1,1]
 
 [[1],[2,3,4]]
@@ -167,4 +154,39 @@ mod test {
 
 [1,[2,[3,[4,[5,6,7]]]],8,9]
 [1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+#[cfg(test)]
+mod test {
+    use crate::day13::Value::{Int, List};
+    use crate::day13::{list, ordered_correctly, parse_packets, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 13;
+        part2_test: 2, SAMPLE_INPUT => 140;
+    }
+
+    #[test]
+    fn parser_test() {
+        let (_, result) = list("[1,1,3,1,[[]]]").unwrap();
+        let expected = List(vec![
+            Int(1),
+            Int(1),
+            Int(3),
+            Int(1),
+            List(vec![List(vec![])]),
+        ]);
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn order_test() {
+        assert!(ordered_correctly(
+            &parse_packets("[1,1,3,1,1]\n[1,1,5,1,1]").unwrap()
+        ));
+        assert!(ordered_correctly(
+            &parse_packets("[[1],[2,3,4]]\n[[1],4]").unwrap()
+        ));
+    }
 }
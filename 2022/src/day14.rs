@@ -1,8 +1,12 @@
 use std::cmp::{max, min};
 use std::collections::HashMap;
 use std::hash::{BuildHasher, BuildHasherDefault};
+use std::io::{self, Write};
+use tracing::{debug, enabled, trace, Level};
 
+use anyhow::{bail, Result};
 use fxhash::{FxBuildHasher, FxHasher};
+use itertools::Itertools;
 use nom::bytes::complete::tag;
 use nom::character::complete::i32 as nom_i32;
 use nom::multi::separated_list1;
@@ -10,19 +14,36 @@ use nom::sequence::separated_pair;
 use nom::IResult;
 
 use crate::day14::Item::{FallingSand, Rock, Sand};
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::puzzle::{ParsedFns, Puzzle};
+use crate::util::parse_all;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 14,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Regolith Reservoir"),
+    parsed: Some(&ParsedFns {
+        parse: |input| build_cave(parse_input(input)?),
+        part1,
+        part2,
+    }),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
 type Point = (i32, i32);
 
-fn part1(input: &str) -> i32 {
-    let rocks = parse_input(input);
-    let mut cave = build_cave(rocks);
+type Cave = HashMap<Point, Item, BuildHasherDefault<FxHasher>>;
+
+fn part1(cave: &Cave) -> Result<i32> {
+    let (cave, sands) = pour_into_abyss(cave);
+    debug_cave(&cave);
+    Ok(sands)
+}
+
+// Both parts fill their own copy of the cave with sand. This one stops when sand starts falling
+// into the abyss, and traces the path of the first grain that does.
+fn pour_into_abyss(cave: &Cave) -> (Cave, i32) {
+    let mut cave = cave.clone();
     let ymax = *cave.keys().map(|(_, y)| y).max().unwrap();
 
     let mut sands = 0;
@@ -31,9 +52,7 @@ fn part1(input: &str) -> i32 {
     }
 
     drop_sand(&mut cave, ymax, true);
-
-    print_cave(&cave);
-    sands
+    (cave, sands)
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -43,39 +62,71 @@ enum Item {
     FallingSand,
 }
 
-fn parse_input(input: &str) -> Vec<Vec<Point>> {
-    input.lines().map(|l| rock(l).unwrap().1).collect()
+// Each line is a path of horizontal and vertical rock lines
+// Sand pours in at (500, 0), so real rocks are never far from there. Anything further away would
+// make the cave, and the floor under it, needlessly big.
+const MAX_COORDINATE: i32 = 1000;
+
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Vec<Point>>> {
+    let rocks: Vec<Vec<Point>> = input
+        .lines()
+        .map(|l| parse_all(rock, l))
+        .collect::<Result<_>>()?;
+    let in_range = |c: &i32| (0..=MAX_COORDINATE).contains(c);
+    if let Some(p) = rocks
+        .iter()
+        .flatten()
+        .find(|(x, y)| !in_range(x) || !in_range(y))
+    {
+        bail!("Rock at {:?} is out of range", p);
+    }
+    for (a, b) in rocks.iter().flat_map(|path| path.iter().tuple_windows()) {
+        if a.0 != b.0 && a.1 != b.1 {
+            bail!("Diagonal rock line {:?} -> {:?}", a, b);
+        }
+    }
+    Ok(rocks)
 }
 
 fn rock(i: &str) -> IResult<&str, Vec<Point>> {
     separated_list1(tag(" -> "), separated_pair(nom_i32, tag(","), nom_i32))(i)
 }
 
-fn build_cave(rocks: Vec<Vec<Point>>) -> HashMap<(i32, i32), Item, BuildHasherDefault<FxHasher>> {
+fn build_cave(rocks: Vec<Vec<Point>>) -> Result<Cave> {
     let mut map = HashMap::with_capacity_and_hasher(30000, FxBuildHasher::default());
     for rock_path in rocks {
-        let mut points = rock_path.iter();
-        let mut a = points.next().unwrap();
-        for b in points {
-            // println!("({a:?},{b:?})");
+        for (a, b) in rock_path.iter().tuple_windows() {
+            trace!(?a, ?b, "Rock line");
+            // parse_input only lets through vertical and horizontal lines
             let rocks: Vec<Point> = if a.0 == b.0 {
                 (min(a.1, b.1)..=max(a.1, b.1)).map(|y| (a.0, y)).collect()
-            } else if a.1 == b.1 {
-                (min(a.0, b.0)..=max(a.0, b.0)).map(|x| (x, a.1)).collect()
             } else {
-                panic!("Unhandled: diagonal rock line {a:?} -> {b:?}")
+                (min(a.0, b.0)..=max(a.0, b.0)).map(|x| (x, a.1)).collect()
             };
             for rock in rocks {
                 map.insert(rock, Rock);
             }
-            a = b;
         }
     }
-    map
+    if map.is_empty() {
+        bail!("No rocks found");
+    }
+    Ok(map)
+}
+
+// Drawing the cave takes a while, so it is only done when it will be shown
+fn debug_cave(cave: &Cave) {
+    if enabled!(Level::DEBUG) {
+        let mut render = vec![];
+        render_cave(&mut render, cave).unwrap();
+        debug!("The cave\n{}", String::from_utf8_lossy(&render));
+    }
 }
 
-fn print_cave<H: BuildHasher>(cave: &HashMap<(i32, i32), Item, H>) {
-    // dbg!(cave);
+fn render_cave<H: BuildHasher>(
+    out: &mut dyn Write,
+    cave: &HashMap<(i32, i32), Item, H>,
+) -> io::Result<()> {
     let (mut xmin, mut xmax, mut ymin, mut ymax) = (i32::MAX, 0, i32::MAX, 0);
     for (x, y) in cave.keys() {
         (xmin, xmax) = (min(xmin, *x), max(xmax, *x));
@@ -90,18 +141,18 @@ fn print_cave<H: BuildHasher>(cave: &HashMap<(i32, i32), Item, H>) {
                 Some(FallingSand) => '~',
                 None => '.',
             };
-            print!("{c}")
+            write!(out, "{c}")?;
         }
-        println!()
+        writeln!(out)?;
     }
-    println!()
+    writeln!(out)
 }
 
 fn drop_sand<H: BuildHasher>(cave: &mut HashMap<Point, Item, H>, ymax: i32, trace: bool) -> bool {
     let (mut x, mut y) = (500, 0);
     let mut path = vec![];
     loop {
-        let ps = vec![(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)];
+        let ps = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)];
         if let Some(next) = ps.iter().find(|p| cave.get(*p).is_none()) {
             (x, y) = *next;
             if trace {
@@ -123,9 +174,15 @@ fn drop_sand<H: BuildHasher>(cave: &mut HashMap<Point, Item, H>, ymax: i32, trac
     true
 }
 
-fn part2(input: &str) -> i32 {
-    let rocks = parse_input(input);
-    let mut cave = build_cave(rocks);
+fn part2(cave: &Cave) -> Result<i32> {
+    let (cave, sands) = pour_onto_floor(cave);
+    debug_cave(&cave);
+    Ok(sands)
+}
+
+// Add the floor, and pour sand until it blocks the source
+fn pour_onto_floor(cave: &Cave) -> (Cave, i32) {
+    let mut cave = cave.clone();
     let ymax = *cave.keys().map(|(_, y)| y).max().unwrap();
 
     // Add a floor that is slightly wider than twice the cave depth
@@ -136,36 +193,62 @@ fn part2(input: &str) -> i32 {
     let mut sands = 0;
     while drop_sand(&mut cave, ymax, false) {
         sands += 1;
-        if cave.get(&(500, 0)).is_some() {
+        if cave.contains_key(&(500, 0)) {
             break;
         }
     }
-
-    print_cave(&cave);
-    sands
+    (cave, sands)
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9";
+
 #[cfg(test)]
 mod test {
-    use crate::day14::{part1, part2, PUZZLE};
+    use crate::day14::{
+        build_cave, parse_input, pour_into_abyss, pour_onto_floor, render_cave, Cave, PUZZLE,
+        SAMPLE_INPUT,
+    };
+    use crate::snapshot::{check_snapshot, Colour};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 24;
+        part2_test: 2, SAMPLE_INPUT => 93;
+    }
+
+    fn check_cave(name: &str, cave: &Cave) {
+        let mut render = vec![];
+        render_cave(&mut render, cave).unwrap();
+        check_snapshot(name, &String::from_utf8(render).unwrap(), Colour::Strip);
+    }
 
     #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 24)
+    fn abyss_snapshot() {
+        let cave = build_cave(parse_input(SAMPLE_INPUT).unwrap()).unwrap();
+        check_cave("day14_abyss", &pour_into_abyss(&cave).0);
     }
 
     #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 93)
+    fn floor_snapshot() {
+        let cave = build_cave(parse_input(SAMPLE_INPUT).unwrap()).unwrap();
+        check_cave("day14_floor", &pour_onto_floor(&cave).0);
     }
 
     #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
+    fn parse_input_test() {
+        assert!(parse_input("498,4 -> 496,6").is_err());
+        assert!(parse_input("498,4 -> 498,-1").is_err());
+        assert!(parse_input("0,0 -> 2000000000,0").is_err());
     }
 
-    const SAMPLE_INPUT: &str = "498,4 -> 498,6 -> 496,6
-503,4 -> 502,4 -> 502,9 -> 494,9";
+    #[test]
+    fn build_cave_test() {
+        let cave = build_cave(parse_input("498,4 -> 498,6 -> 496,6").unwrap()).unwrap();
+        assert_eq!(cave.len(), 5);
+        assert!(build_cave(parse_input("498,4").unwrap()).is_err());
+        assert!(build_cave(vec![]).is_err());
+    }
 }
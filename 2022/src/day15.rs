@@ -1,19 +1,41 @@
-use crate::puzzle::Puzzle;
+use crate::parallel::par_find_first;
 use crate::puzzle::PuzzleFn::{U64, USIZE};
+use crate::puzzle::{Puzzle, Variant};
+use crate::util::parse_all;
+use anyhow::{anyhow, Result};
 use fxhash::FxHashSet;
 use nom::bytes::complete::tag;
 use nom::character::complete::i32 as parse_i32;
 use nom::sequence::{pair, preceded};
 use nom::IResult;
+use std::iter;
+use tracing::debug;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 15,
+    title: Some("Beacon Exclusion Zone"),
+    notes: Some("Part 2 scans every row for the one gap in sensor coverage"),
     part1: USIZE(|i| part1(i, 2000000)),
     part2: U64(|i| part2(i, 4000000)),
+    // No sample, since the example uses a smaller row and search area than the real input
+    variants: &[
+        Variant {
+            name: "intervals",
+            part: 1,
+            solve: USIZE(|i| count_by_intervals(i, 2000000)),
+        },
+        Variant {
+            name: "perimeter",
+            part: 2,
+            solve: U64(|i| found(find_beacon_on_perimeter(i, 4000000)?)),
+        },
+    ],
+    ..Puzzle::DEFAULT
 };
 
-fn part1(input: &str, y: i32) -> usize {
-    let sensor_data = parse_input(input);
+fn part1(input: &str, y: i32) -> Result<usize> {
+    let sensor_data = parse_input(input)?;
     let mut row: FxHashSet<i32> = FxHashSet::default();
 
     for (sensor, beacon) in sensor_data {
@@ -30,33 +52,117 @@ fn part1(input: &str, y: i32) -> usize {
         }
     }
 
-    row.len()
+    Ok(row.len())
 }
 
-fn part2(input: &str, bound: i32) -> u64 {
-    let sensor_data = parse_input(input);
+// Merge the stretch of row y that each sensor covers, rather than visiting every point
+fn count_by_intervals(input: &str, y: i32) -> Result<usize> {
+    let sensor_data = parse_input(input)?;
+    let mut ranges: Vec<(i32, i32)> = sensor_data
+        .iter()
+        .filter_map(|(sensor, beacon)| {
+            let reach = manhattan_dist(sensor, beacon) - (y - sensor.1).abs();
+            (reach >= 0).then_some((sensor.0 - reach, sensor.0 + reach))
+        })
+        .collect();
+    ranges.sort_unstable();
+
+    let (mut covered, mut end) = (0, i32::MIN);
+    for (start, stop) in ranges {
+        let start = start.max(end.saturating_add(1));
+        if stop >= start {
+            covered += (stop - start + 1) as usize;
+            end = stop;
+        }
+    }
+    // Positions that already hold a beacon don't count
+    let beacons: FxHashSet<Point> = sensor_data
+        .iter()
+        .map(|(_, beacon)| *beacon)
+        .filter(|beacon| beacon.1 == y)
+        .collect();
+    Ok(covered - beacons.len())
+}
+
+fn part2(input: &str, bound: i32) -> Result<u64> {
+    found(find_beacon(input, bound)?)
+}
+
+// The tuning frequency of the distress beacon, which every input should have
+fn found(beacon: Option<Point>) -> Result<u64> {
+    beacon
+        .map(tuning_frequency)
+        .ok_or_else(|| anyhow!("There is no gap in the sensor coverage"))
+}
+
+fn tuning_frequency((x, y): Point) -> u64 {
+    x as u64 * 4000000 + y as u64
+}
+
+// Find the first point, by row, within 0..=bound that is out of range of every sensor
+fn find_beacon(input: &str, bound: i32) -> Result<Option<Point>> {
+    let sensor_data = parse_input(input)?;
     let sensor_ranges: Vec<(&Point, i32)> = sensor_data
         .iter()
         .map(|(sensor, beacon)| (sensor, manhattan_dist(sensor, beacon)))
         .collect();
 
-    for y in 0..=bound {
-        let mut x = 0;
-        'row: while x <= bound {
-            for (sensor, range) in &sensor_ranges {
-                if manhattan_dist(sensor, &(x as i32, y as i32)) <= *range {
-                    // In range of sensor. Skip to the next point on the same row that is out of range
-                    let y_dist = (y - sensor.1).abs();
-                    x = sensor.0 + (range - y_dist) + 1;
-                    continue 'row;
-                }
+    // Each row is scanned independently
+    let beacon = par_find_first(0..=bound, |y| scan_row(&sensor_ranges, y, bound));
+    if let Some((x, y)) = beacon {
+        debug!(x, y, "Distress beacon");
+    }
+    Ok(beacon)
+}
+
+// Find the point on row y that is out of range of every sensor, if there is one
+fn scan_row(sensor_ranges: &[(&Point, i32)], y: i32, bound: i32) -> Option<Point> {
+    let mut x = 0;
+    'row: while x <= bound {
+        for (sensor, range) in sensor_ranges {
+            if manhattan_dist(sensor, &(x, y)) <= *range {
+                // In range of sensor. Skip to the next point on the same row that is out of range
+                let y_dist = (y - sensor.1).abs();
+                x = sensor.0 + (range - y_dist) + 1;
+                continue 'row;
             }
-            // No sensors in range. This is it!
-            // println!("Distress beacon: {x},{y}");
-            return x as u64 * 4000000 + y as u64;
         }
+        // No sensors in range. This is it!
+        return Some((x, y));
     }
-    panic!("No solution found!");
+    None
+}
+
+// The first point, by row, that is out of range must be just outside some sensor's range (or
+// else the point before it or above it would be out of range too), unless it is the corner of
+// the search area. So only those points need checking.
+fn find_beacon_on_perimeter(input: &str, bound: i32) -> Result<Option<Point>> {
+    let sensor_data = parse_input(input)?;
+    let sensor_ranges: Vec<(Point, i32)> = sensor_data
+        .iter()
+        .map(|(sensor, beacon)| (*sensor, manhattan_dist(sensor, beacon)))
+        .collect();
+
+    let perimeters = sensor_ranges.iter().flat_map(|&((x, y), range)| {
+        let d = range + 1;
+        (0..d).flat_map(move |i| {
+            [
+                (x + i, y - d + i),
+                (x + d - i, y + i),
+                (x - i, y + d - i),
+                (x - d + i, y - i),
+            ]
+        })
+    });
+    Ok(iter::once((0, 0))
+        .chain(perimeters)
+        .filter(|(x, y)| (0..=bound).contains(x) && (0..=bound).contains(y))
+        .filter(|p| {
+            sensor_ranges
+                .iter()
+                .all(|(sensor, range)| manhattan_dist(sensor, p) > *range)
+        })
+        .min_by_key(|&(x, y)| (y, x)))
 }
 
 fn manhattan_dist(a: &Point, b: &Point) -> i32 {
@@ -65,8 +171,8 @@ fn manhattan_dist(a: &Point, b: &Point) -> i32 {
 
 type Point = (i32, i32);
 
-fn parse_input(input: &str) -> Vec<(Point, Point)> {
-    input.lines().map(|l| parse_sensor(l).unwrap().1).collect()
+pub(crate) fn parse_input(input: &str) -> Result<Vec<(Point, Point)>> {
+    input.lines().map(|l| parse_all(parse_sensor, l)).collect()
 }
 
 fn parse_sensor(i: &str) -> IResult<&str, (Point, Point)> {
@@ -86,23 +192,113 @@ fn parse_sensor(i: &str) -> IResult<&str, (Point, Point)> {
 
 #[cfg(test)]
 mod test {
-    use crate::day15::{part1, part2, PUZZLE};
+    use crate::day15::{
+        count_by_intervals, find_beacon, find_beacon_on_perimeter, manhattan_dist, parse_input,
+        part1, part2, Point, PUZZLE,
+    };
+    use crate::testing::{differential, puzzle_tests};
+    use itertools::Itertools;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    // The sample uses a smaller search area than the real input, so it can't go through PUZZLE
+    puzzle_tests! {
+        PUZZLE;
+    }
 
     #[test]
     fn part1_test() {
-        let answer = part1(SAMPLE_INPUT, 10);
+        let answer = part1(SAMPLE_INPUT, 10).unwrap();
         assert_eq!(answer, 26)
     }
 
     #[test]
     fn part2_test() {
-        let answer = part2(SAMPLE_INPUT, 20);
+        let answer = part2(SAMPLE_INPUT, 20).unwrap();
         assert_eq!(answer, 56000011)
     }
 
     #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
+    fn variants_test() {
+        assert_eq!(count_by_intervals(SAMPLE_INPUT, 10).unwrap(), 26);
+        assert_eq!(
+            find_beacon_on_perimeter(SAMPLE_INPUT, 20).unwrap(),
+            Some((14, 11))
+        );
+    }
+
+    // Search area for the generated layouts
+    const BOUND: i32 = 20;
+
+    // Random sensors around the search area, each reporting the closest of some random beacons.
+    // As in the puzzle, a sensor's closest beacon is never tied with another.
+    fn sensor_layouts() -> impl Strategy<Value = String> {
+        let point = || (-10..30, -10..30);
+        (vec(point(), 1..10), vec(point(), 1..6)).prop_filter_map(
+            "sensor is equally close to two beacons",
+            |(sensors, beacons)| {
+                let lines = sensors.iter().map(|sensor| {
+                    let dists = beacons.iter().map(|b| (manhattan_dist(sensor, b), *b));
+                    let closest = dists.min_set_by_key(|(dist, _)| *dist);
+                    let [(_, beacon)] = closest[..] else {
+                        return None;
+                    };
+                    Some(format!(
+                        "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                        sensor.0, sensor.1, beacon.0, beacon.1
+                    ))
+                });
+                lines.collect::<Option<Vec<String>>>().map(|l| l.join("\n"))
+            },
+        )
+    }
+
+    fn in_range(sensors: &[(Point, Point)], p: Point) -> bool {
+        sensors
+            .iter()
+            .any(|(s, b)| manhattan_dist(s, &p) <= manhattan_dist(s, b))
+    }
+
+    // Check every point on the row that any sensor could reach
+    fn count_excluded(input: &str, y: i32) -> usize {
+        let sensors = parse_input(input).unwrap();
+        (-120..=120)
+            .filter(|&x| in_range(&sensors, (x, y)) && sensors.iter().all(|(_, b)| *b != (x, y)))
+            .count()
+    }
+
+    // Check every point in the search area
+    fn search_area(input: &str) -> Option<Point> {
+        let sensors = parse_input(input).unwrap();
+        (0..=BOUND)
+            .flat_map(|y| (0..=BOUND).map(move |x| (x, y)))
+            .find(|&p| !in_range(&sensors, p))
+    }
+
+    #[test]
+    fn part1_differential() {
+        for y in [0, 10, 25] {
+            differential(
+                sensor_layouts(),
+                |i| count_excluded(i, y),
+                |i| part1(i, y).unwrap(),
+            );
+            differential(
+                sensor_layouts(),
+                |i| count_excluded(i, y),
+                |i| count_by_intervals(i, y).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn part2_differential() {
+        differential(sensor_layouts(), search_area, |i| {
+            find_beacon(i, BOUND).unwrap()
+        });
+        differential(sensor_layouts(), search_area, |i| {
+            find_beacon_on_perimeter(i, BOUND).unwrap()
+        });
     }
 
     const SAMPLE_INPUT: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
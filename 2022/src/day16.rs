@@ -1,29 +1,39 @@
-use fxhash::FxHashMap;
+use anyhow::{bail, Result};
 use itertools::Itertools;
+use lazy_static::lazy_static;
 use nom::bytes::complete::tag;
 use nom::character::complete::alpha1;
 use nom::character::complete::i32 as parse_i32;
 use nom::combinator::map as nom_map;
-use nom::error::ErrorKind;
 use nom::multi::separated_list1;
 use nom::sequence::preceded;
 use nom::IResult;
 use nom_regex::str::re_find;
 use regex::Regex;
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::hash::Hash;
+use tracing::debug;
 
-use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use crate::parallel::par_max;
+use crate::puzzle::{ParsedFns, Puzzle};
+use crate::util::parse_all;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 16,
-    part1: I32(part1),
-    part2: I32(part2),
+    title: Some("Proboscidea Volcanium"),
+    notes: Some("Part 2 tries every even split of the valves between you and the elephant"),
+    parsed: Some(&ParsedFns {
+        parse: |input| Ok(build_cave(parse_input(input)?)),
+        part1,
+        part2,
+    }),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
 #[derive(Clone, Debug)]
-struct Valve {
+pub(crate) struct Valve {
     id: String,
     flow_rate: i32,
     connections: Vec<String>,
@@ -39,8 +49,10 @@ struct State {
 
 type Matrix<T> = Vec<Vec<T>>;
 
-fn build_cave(input: &str) -> Cave {
-    let valves = parse_input(input);
+// Where you and the elephant start
+const START: &str = "AA";
+
+fn build_cave(valves: Vec<Valve>) -> Cave {
     // Valves with 0 flow are not considered as nodes in the graph, but only contribute to the path
     // distance between functioning valves
     let flowable_valves: Vec<usize> = valves
@@ -54,7 +66,10 @@ fn build_cave(input: &str) -> Cave {
     // each valve to each of the other valves.
     // each functioning valve will be an index in the matrix rather than a string
     let adj = min_dists(edges, valves.len());
+    // parse_input checks that the start is there
+    let start = valves.iter().position(|v| v.id == START).unwrap();
     Cave {
+        start,
         valves,
         flowable_valves,
         adj,
@@ -82,8 +97,8 @@ fn min_dists(edges: Vec<(usize, usize)>, n_nodes: usize) -> Matrix<i32> {
         dist[u][v] = 1; // All of the direct connections have distance 1
     }
 
-    for v in 0..n_nodes {
-        dist[v][v] = 0; // Distnace from a node to itself is obviously zero
+    for (v, row) in dist.iter_mut().enumerate() {
+        row[v] = 0; // Distnace from a node to itself is obviously zero
     }
 
     for k in 0..n_nodes {
@@ -100,6 +115,7 @@ fn min_dists(edges: Vec<(usize, usize)>, n_nodes: usize) -> Matrix<i32> {
 }
 
 struct Cave {
+    start: usize,
     valves: Vec<Valve>,
     flowable_valves: Vec<usize>,
     adj: Matrix<i32>,
@@ -117,27 +133,19 @@ impl Cave {
     }
 }
 
-fn part1(input: &str) -> i32 {
-    let cave = build_cave(input);
+fn part1(cave: &Cave) -> Result<i32> {
     let initial_state = State {
-        location: 0,
+        location: cave.start,
         open_valves: vec![],
         eventual_pressure: 0,
         time_remaining: 30,
     };
-    max_pressure(&cave, initial_state)
+    Ok(max_pressure(cave, initial_state))
 }
 
-// thread_local! {
-//     static PRESSURES: RefCell<FxHashMap<State, i32>> = RefCell::new(FxHashMap::default());
-// }
-
 fn max_pressure(cave: &Cave, state: State) -> i32 {
     if state.time_remaining == 0 {
         state.eventual_pressure
-    // } else if let Some(cached) = PRESSURES.with(|p| p.borrow().get(&state).cloned()) {
-    //     println!("Cache hit! {:?}", cached);
-    //     cached
     } else {
         let max_pressure = cave
             .openable_valves(&state)
@@ -168,14 +176,12 @@ fn max_pressure(cave: &Cave, state: State) -> i32 {
             })
             .max()
             .unwrap_or(state.eventual_pressure);
-        // PRESSURES.with(|p| p.borrow_mut().insert(state, max_pressure));
         max_pressure
     }
 }
 
 // 2622 is too high
-fn part2(input: &str) -> i32 {
-    let cave = build_cave(input);
+fn part2(cave: &Cave) -> Result<i32> {
     let valves_sets: Vec<(Vec<usize>, Vec<usize>)> = cave
         .flowable_valves
         .iter()
@@ -190,49 +196,64 @@ fn part2(input: &str) -> i32 {
             (my_valves.iter().map(|&v| *v).collect(), elephant_valves)
         })
         .collect();
-    dbg!(&valves_sets.len());
-    valves_sets
-        .iter()
-        .map(|(my_valves, elephant_valves)| {
-            let me = max_pressure(
-                &cave,
-                State {
-                    location: 0,
-                    open_valves: elephant_valves.clone(),
-                    eventual_pressure: 0,
-                    time_remaining: 26,
-                },
-            );
-            let elephant = max_pressure(
-                &cave,
-                State {
-                    location: 0,
-                    open_valves: my_valves.clone(),
-                    eventual_pressure: 0,
-                    time_remaining: 26,
-                },
-            );
-            me + elephant
-        })
-        .max()
-        .unwrap()
+    debug!(splits = valves_sets.len(), "Splitting the valves");
+    // Each way of splitting the valves is evaluated independently. There is always at least one.
+    Ok(par_max(&valves_sets, |(my_valves, elephant_valves)| {
+        let me = max_pressure(
+            cave,
+            State {
+                location: cave.start,
+                open_valves: elephant_valves.clone(),
+                eventual_pressure: 0,
+                time_remaining: 26,
+            },
+        );
+        let elephant = max_pressure(
+            cave,
+            State {
+                location: cave.start,
+                open_valves: my_valves.clone(),
+                eventual_pressure: 0,
+                time_remaining: 26,
+            },
+        );
+        me + elephant
+    })
+    .unwrap())
 }
 
-fn parse_input(input: &str) -> Vec<Valve> {
-    input
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Valve>> {
+    let valves: Vec<Valve> = input
         .lines()
-        .map(|l| parse_valve(l).unwrap().1)
+        .map(|l| parse_all(parse_valve, l))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
         .sorted_by(|a, b| a.id.cmp(&b.id))
-        .collect()
+        .collect();
+    if !valves.iter().any(|v| v.id == START) {
+        bail!("There is no valve {} to start from", START);
+    }
+    if let Some((a, _)) = valves.iter().tuple_windows().find(|(a, b)| a.id == b.id) {
+        bail!("Valve {} is listed twice", a.id);
+    }
+    for valve in &valves {
+        for c in &valve.connections {
+            if valves.binary_search_by(|v| v.id.cmp(c)).is_err() {
+                bail!("Valve {} leads to {}, which doesn't exist", valve.id, c);
+            }
+        }
+    }
+    Ok(valves)
 }
 
 fn parse_valve(i: &str) -> IResult<&str, Valve> {
     // Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
     let (i, id) = preceded(tag("Valve "), nom_map(alpha1, str::to_string))(i)?;
     let (i, flow_rate) = preceded(tag(" has flow rate="), parse_i32)(i)?;
-    let (i, _) =
-        re_find::<(&str, ErrorKind)>(Regex::new(r"; tunnels? leads? to valves? ").unwrap())(i)
-            .unwrap();
+    lazy_static! {
+        static ref TUNNELS: Regex = Regex::new(r"^; tunnels? leads? to valves? ").unwrap();
+    }
+    let (i, _) = re_find(TUNNELS.clone())(i)?;
     let (i, connections) = separated_list1(tag(", "), nom_map(alpha1, str::to_string))(i)?;
     Ok((
         i,
@@ -244,9 +265,28 @@ fn parse_valve(i: &str) -> IResult<&str, Valve> {
     ))
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
+
 #[cfg(test)]
 mod test {
-    use crate::day16::{edges, min_dists, parse_input, parse_valve, part1, part2, PUZZLE};
+    use crate::day16::{edges, min_dists, parse_input, parse_valve, PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
+
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 1651;
+        part2_test: 2, SAMPLE_INPUT => 1707;
+    }
 
     #[test]
     fn parse_valve_test() {
@@ -261,9 +301,18 @@ mod test {
         assert_eq!(v1.connections, vec!["GG"]);
     }
 
+    #[test]
+    fn start_test() {
+        assert!(PUZZLE.prepare(&SAMPLE_INPUT.replace("AA", "AZ")).is_err());
+        // The start needn't be the first valve
+        let input = format!("Valve A has flow rate=0; tunnel leads to valve AA\n{SAMPLE_INPUT}");
+        let prepared = PUZZLE.prepare(&input).unwrap();
+        assert_eq!(PUZZLE.solve_prepared(1, &prepared).unwrap().answer, "1651");
+    }
+
     #[test]
     fn edges_test() {
-        let valves = parse_input(SAMPLE_INPUT);
+        let valves = parse_input(SAMPLE_INPUT).unwrap();
         let edges = edges(&valves);
         assert_eq!(edges.len(), 20);
         assert!(edges.contains(&(0, 1)));
@@ -273,37 +322,9 @@ mod test {
 
     #[test]
     fn min_dists_test() {
-        let valves = parse_input(SAMPLE_INPUT);
+        let valves = parse_input(SAMPLE_INPUT).unwrap();
         let edges = edges(&valves);
         let adj = min_dists(edges, valves.len());
         assert_eq!(adj[0], vec![0, 1, 2, 1, 2, 3, 4, 5, 1, 2]);
     }
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 1651)
-    }
-
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 1707)
-    }
-
-    #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
-    }
-
-    const SAMPLE_INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
-Valve BB has flow rate=13; tunnels lead to valves CC, AA
-Valve CC has flow rate=2; tunnels lead to valves DD, BB
-Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
-Valve EE has flow rate=3; tunnels lead to valves FF, DD
-Valve FF has flow rate=0; tunnels lead to valves EE, GG
-Valve GG has flow rate=0; tunnels lead to valves FF, HH
-Valve HH has flow rate=22; tunnel leads to valve GG
-Valve II has flow rate=0; tunnels lead to valves AA, JJ
-Valve JJ has flow rate=21; tunnel leads to valve II";
 }
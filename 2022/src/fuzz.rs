@@ -0,0 +1,87 @@
+use crate::{day01, day02, day03, day04, day05, day06, day07, day08};
+use crate::{day09, day10, day11, day12, day13, day14, day15, day16};
+use anyhow::{bail, Result};
+
+// Entry point for the fuzz targets in fuzz/. Runs the parser for a day over arbitrary input, which
+// should return an error rather than panic when the input isn't valid. Days with a parse stage run
+// all of it, including building the model from what was parsed.
+#[doc(hidden)]
+pub fn parse(day: i32, input: &str) -> Result<()> {
+    match day {
+        1 => lines(input, day01::parse_calories),
+        2 => lines(input, day02::parse_round),
+        3 => lines(input, |line| day03::parse_rucksack(line).map(drop)),
+        4 => lines(input, day04::parse_elves),
+        5 => day05::parse_input(input).map(drop),
+        // There is nothing to parse, so the marker search is run instead
+        6 => day06::find_marker(&mut input.as_bytes(), 14).map(drop),
+        7 => day07::parse_input(input).map(drop),
+        8 => day08::parse_matrix(input).map(drop),
+        9 => day09::parse_input(input).map(drop),
+        10 => day10::parse_input(input).map(drop),
+        11 => day11::PUZZLE.prepare(input).map(drop),
+        12 => day12::PUZZLE.prepare(input).map(drop),
+        13 => day13::parse_input(input).map(drop),
+        14 => day14::PUZZLE.prepare(input).map(drop),
+        15 => day15::parse_input(input).map(drop),
+        16 => day16::PUZZLE.prepare(input).map(drop),
+        _ => bail!("No parser for day {}", day),
+    }
+}
+
+fn lines<T>(input: &str, parse_line: impl Fn(&str) -> Result<T>) -> Result<()> {
+    input
+        .lines()
+        .try_for_each(|line| parse_line(line).map(drop))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzz::parse;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Every prefix of every sample in the seed corpus is an easy source of broken inputs
+    #[test]
+    fn corpus_prefixes_test() {
+        let corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus");
+        for day in 1..=16 {
+            let dir = corpus.join(format!("day{:02}", day));
+            let entries = fs::read_dir(&dir).unwrap();
+            // The fuzzer adds its own inputs to the corpus, which needn't be valid
+            let samples = entries.map(|entry| entry.unwrap().path()).filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("sample")
+            });
+            for path in samples {
+                let input = fs::read_to_string(path).unwrap();
+                if let Err(e) = parse(day, &input) {
+                    panic!("day {} failed to parse: {:#}", day, e);
+                }
+                for (i, _) in input.char_indices() {
+                    let _ = parse(day, &input[..i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn garbage_test() {
+        for day in 1..=16 {
+            for input in [
+                "",
+                "\n",
+                "\n\n\n",
+                "é",
+                "[[[[",
+                "-1",
+                "x -> 0,0",
+                "Monkey 0:",
+            ] {
+                let _ = parse(day, input);
+            }
+        }
+    }
+}
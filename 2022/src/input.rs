@@ -0,0 +1,347 @@
+use crate::puzzle::PuzzleId;
+use crate::session::{is_login_error, Session};
+use crate::util::format_duration;
+use anyhow::{bail, Context, Result};
+use futures::future::join_all;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+// adventofcode.com asks that automated requests identify where they come from
+const USER_AGENT: &str = concat!(
+    "github.com/fotoetienne/advent ",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+);
+
+// Minimum time between two requests to adventofcode.com
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Inputs are cached in $AOC_INPUT_DIR if it is set, and otherwise in the inputs directory of the
+/// directory we are run from. cargo runs the binary and the tests from the crate directory.
+pub fn default_cache_dir() -> PathBuf {
+    match env::var_os("AOC_INPUT_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from("inputs"),
+    }
+}
+
+// Requests go to $AOC_URL if it is set, e.g. to use a mirror or a local server for testing
+fn default_base_url() -> String {
+    env::var("AOC_URL").unwrap_or_else(|_| "https://adventofcode.com".to_string())
+}
+
+/// Reads puzzle inputs from the cache directory, fetching and caching any that are missing.
+/// A single runtime and http client are shared by every request.
+pub struct InputService {
+    runtime: Runtime,
+    client: reqwest::Client,
+    base_url: String,
+    cache_dir: PathBuf,
+    // Session token given on the command line, if any
+    session_flag: Option<String>,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl InputService {
+    pub fn new(cache_dir: PathBuf, session_flag: Option<String>) -> Result<Self> {
+        let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+        Ok(InputService {
+            runtime: Runtime::new()?,
+            client,
+            base_url: default_base_url(),
+            cache_dir,
+            session_flag,
+            last_request: Mutex::new(None),
+        })
+    }
+
+    /// Get the inputs for several days at once. Missing inputs are fetched concurrently, subject
+    /// to the rate limit. A day that can't be fetched doesn't stop the others.
+    pub fn prefetch(&self, ids: &[PuzzleId]) -> Vec<Result<String>> {
+        let inputs = ids.iter().map(|&id| self.read_or_fetch_input(id));
+        self.runtime.block_on(join_all(inputs))
+    }
+
+    // Check that the session is valid by fetching (but not caching) the first day's input
+    pub(crate) fn check_login(&self, year: i32) -> Result<Session> {
+        let session = Session::find(self.session_flag.as_deref())?;
+        let id = PuzzleId { year, day: 1 };
+        self.runtime.block_on(self.fetch_input(&session, id))?;
+        Ok(session)
+    }
+
+    /// Fetch the inputs for the given days again, replacing the cached ones. The cache is only
+    /// touched once an input has been fetched successfully, and a day that can't be fetched
+    /// doesn't stop the others.
+    pub fn refresh(&self, ids: &[PuzzleId]) -> Result<Vec<Result<String>>> {
+        let session = Session::find(self.session_flag.as_deref())?;
+        let inputs = ids.iter().map(|&id| self.fetch_and_cache(&session, id));
+        Ok(self.runtime.block_on(join_all(inputs)))
+    }
+
+    // Get the puzzle description pages for several days. Pages that aren't cached yet are only
+    // fetched if `fetch` is set, and are None otherwise.
+    pub(crate) fn get_pages(&self, ids: &[PuzzleId], fetch: bool) -> Vec<Result<Option<String>>> {
+        let pages = ids.iter().map(|&id| self.read_or_fetch_page(id, fetch));
+        self.runtime.block_on(join_all(pages))
+    }
+
+    async fn read_or_fetch_page(&self, id: PuzzleId, fetch: bool) -> Result<Option<String>> {
+        let filename = self.page_filename(id);
+        if let Ok(page) = tokio::fs::read_to_string(&filename).await {
+            return Ok(Some(page));
+        }
+        if !fetch {
+            return Ok(None);
+        }
+        check_unlocked(id)?;
+        // The page can be read without logging in, but then it doesn't include part 2
+        let session = Session::find(self.session_flag.as_deref()).ok();
+        let (status, body) = self
+            .get(session.as_ref(), &page_uri(&self.base_url, id))
+            .await?;
+        if !(200..300).contains(&status) {
+            bail!(
+                "Unable to fetch the puzzle page for {}: status {}",
+                id,
+                status
+            );
+        }
+        tokio::fs::create_dir_all(filename.parent().unwrap()).await?;
+        tokio::fs::write(&filename, &body).await?;
+        Ok(Some(body))
+    }
+
+    fn page_filename(&self, id: PuzzleId) -> PathBuf {
+        self.cache_dir
+            .join(id.year.to_string())
+            .join(format!("puzzle{:02}.html", id.day))
+    }
+
+    async fn read_or_fetch_input(&self, id: PuzzleId) -> Result<String> {
+        if let Some(input) = cached_input(&self.cache_dir, id) {
+            return Ok(input);
+        }
+        // Older versions cached whatever the server sent back, including error pages
+        let filename = self.input_filename(id);
+        if filename.exists() {
+            eprintln!(
+                "Ignoring {}, which contains an error page rather than an input",
+                filename.display()
+            );
+        }
+        let session = Session::find(self.session_flag.as_deref())?;
+        self.fetch_and_cache(&session, id).await
+    }
+
+    async fn fetch_and_cache(&self, session: &Session, id: PuzzleId) -> Result<String> {
+        let input = self.fetch_input(session, id).await?;
+        let filename = self.input_filename(id);
+        tokio::fs::create_dir_all(filename.parent().unwrap()).await?;
+        tokio::fs::write(filename, &input).await?;
+        Ok(input)
+    }
+
+    fn input_filename(&self, id: PuzzleId) -> PathBuf {
+        input_filename(&self.cache_dir, id)
+    }
+
+    async fn fetch_input(&self, session: &Session, id: PuzzleId) -> Result<String> {
+        check_unlocked(id)?;
+        let (status, body) = self
+            .get(Some(session), &input_uri(&self.base_url, id))
+            .await
+            .with_context(|| format!("Unable to fetch input for {}", id))?;
+        if is_login_error(status, &body) {
+            bail!(
+                "Not logged in. The session token from {} is invalid or has expired",
+                session.source
+            );
+        }
+        if let Some(error) = error_body(&body) {
+            bail!("Unable to fetch input for {}: {}", id, error);
+        }
+        if !(200..300).contains(&status) {
+            bail!("Unable to fetch input for {}: status {}", id, status);
+        }
+        Ok(body)
+    }
+
+    async fn get(&self, session: Option<&Session>, uri: &str) -> Result<(u16, String)> {
+        self.wait_for_turn().await;
+        let mut request = self.client.get(uri);
+        if let Some(session) = session {
+            request = request.header("Cookie", session.cookie());
+        }
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        Ok((status, response.text().await?))
+    }
+
+    // Wait until enough time has passed since the previous request
+    async fn wait_for_turn(&self) {
+        let mut last_request = self.last_request.lock().await;
+        if let Some(last) = *last_request {
+            tokio::time::sleep_until(last + REQUEST_INTERVAL).await;
+        }
+        *last_request = Some(Instant::now());
+    }
+}
+
+fn input_filename(cache_dir: &Path, id: PuzzleId) -> PathBuf {
+    cache_dir
+        .join(id.year.to_string())
+        .join(format!("input{:02}.txt", id.day))
+}
+
+// Inputs used to be cached without a year directory, back when they were all for 2022
+const UNDATED_YEAR: i32 = 2022;
+
+fn undated_input_filename(cache_dir: &Path, id: PuzzleId) -> Option<PathBuf> {
+    (id.year == UNDATED_YEAR).then(|| cache_dir.join(format!("input{:02}.txt", id.day)))
+}
+
+/// The cached input for a day, if there is one, without fetching anything
+pub fn cached_input(cache_dir: &Path, id: PuzzleId) -> Option<String> {
+    let input = fs::read_to_string(input_filename(cache_dir, id))
+        .ok()
+        .or_else(|| fs::read_to_string(undated_input_filename(cache_dir, id)?).ok())?;
+    error_body(&input).is_none().then_some(input)
+}
+
+fn input_uri(base_url: &str, id: PuzzleId) -> String {
+    format!("{}/{}/day/{}/input", base_url, id.year, id.day)
+}
+
+fn page_uri(base_url: &str, id: PuzzleId) -> String {
+    format!("{}/{}/day/{}", base_url, id.year, id.day)
+}
+
+fn check_unlocked(id: PuzzleId) -> Result<()> {
+    if let Ok(remaining) = unlock_time(id.year, id.day).duration_since(SystemTime::now()) {
+        bail!(
+            "{} hasn't unlocked yet. Try again in {}",
+            id,
+            format_duration(remaining)
+        );
+    }
+    Ok(())
+}
+
+// Recognize the error pages that adventofcode.com sends back instead of an input
+fn error_body(body: &str) -> Option<&'static str> {
+    let start = body.trim_start();
+    if body.starts_with("Please don't repeatedly request this endpoint before it unlocks!") {
+        Some("the puzzle hasn't unlocked yet")
+    } else if body.contains("Please log in to get your puzzle input") {
+        Some("not logged in")
+    } else if start.starts_with("<!DOCTYPE") || start.starts_with("<html") {
+        Some("received a web page instead of an input")
+    } else if start.starts_with("500 Internal Server Error") {
+        Some("server error")
+    } else if body.is_empty() {
+        Some("empty input")
+    } else {
+        None
+    }
+}
+
+// Puzzles unlock at midnight EST (UTC-5) on the day of December
+fn unlock_time(year: i32, day: i32) -> SystemTime {
+    let days = days_from_civil(year, 12, day);
+    let seconds = days * 24 * 60 * 60 + 5 * 60 * 60;
+    UNIX_EPOCH + Duration::from_secs(seconds as u64)
+}
+
+// Number of days between 1970-01-01 and the given date
+// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i32, month: i32, day: i32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input::{cached_input, error_body, unlock_time, InputService};
+    use crate::puzzle::PuzzleId;
+    use std::env;
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn cached_input_test() {
+        let cache_dir = env::temp_dir().join(format!("advent2022-inputs-{}", std::process::id()));
+        fs::create_dir_all(cache_dir.join("2022")).unwrap();
+        fs::write(cache_dir.join("2022/input03.txt"), "cached").unwrap();
+
+        let service = InputService::new(cache_dir.clone(), None).unwrap();
+        let id = PuzzleId { year: 2022, day: 3 };
+        let inputs: Vec<String> = service
+            .prefetch(&[id, id])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(inputs, vec!["cached", "cached"]);
+        assert_eq!(cached_input(&cache_dir, id).as_deref(), Some("cached"));
+        fs::write(cache_dir.join("2022/input04.txt"), "<!DOCTYPE html>").unwrap();
+        assert_eq!(
+            cached_input(&cache_dir, PuzzleId { year: 2022, day: 4 }),
+            None
+        );
+        assert_eq!(
+            cached_input(&cache_dir, PuzzleId { year: 2022, day: 5 }),
+            None
+        );
+        // Inputs cached before there were year directories are still found
+        fs::write(cache_dir.join("input06.txt"), "undated").unwrap();
+        assert_eq!(
+            cached_input(&cache_dir, PuzzleId { year: 2022, day: 6 }).as_deref(),
+            Some("undated")
+        );
+        assert_eq!(
+            cached_input(&cache_dir, PuzzleId { year: 2021, day: 6 }),
+            None
+        );
+
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn error_body_test() {
+        assert_eq!(error_body("1000\n2000\n"), None);
+        assert_eq!(error_body("[1,[2,3]]\n"), None);
+        assert!(error_body("Please don't repeatedly request this endpoint before it unlocks! The calendar countdown is synchronized with the server time; the link will be enabled on the calendar the instant this puzzle becomes available.\n").is_some());
+        assert!(error_body(
+            "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n"
+        )
+        .is_some());
+        assert!(error_body("<!DOCTYPE html>\n<html lang=\"en-us\">").is_some());
+        assert!(error_body("").is_some());
+    }
+
+    #[test]
+    fn unlock_time_test() {
+        // 2022-12-01T05:00:00Z
+        assert_eq!(
+            unlock_time(2022, 1),
+            UNIX_EPOCH + Duration::from_secs(1669870800)
+        );
+        // 2022-12-25T05:00:00Z
+        assert_eq!(
+            unlock_time(2022, 25),
+            UNIX_EPOCH + Duration::from_secs(1671944400)
+        );
+    }
+}
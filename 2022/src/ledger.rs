@@ -0,0 +1,248 @@
+use crate::puzzle::{PartResult, PuzzleId};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Records the accepted answer and the most recent run of each part of each puzzle.
+/// Stored as a tab separated file so that it is easy to read, edit and diff by hand.
+pub struct Ledger {
+    path: PathBuf,
+    entries: BTreeMap<(PuzzleId, u8), Entry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    /// The answer that adventofcode.com accepted
+    pub expected: Option<String>,
+    pub last_run: Option<LastRun>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LastRun {
+    pub answer: String,
+    pub duration: Duration,
+    /// Seconds since the unix epoch
+    pub run_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartStatus {
+    // The last run gave the accepted answer
+    Solved,
+    // The last run gave something other than the accepted answer
+    Failing,
+    // There is no accepted answer yet
+    Unsolved,
+}
+
+const HEADER: &str = "puzzle\tpart\texpected\tanswer\tms\trun_at";
+
+impl Ledger {
+    pub fn file(dir: &Path) -> PathBuf {
+        dir.join("answers.tsv")
+    }
+
+    /// Load the ledger, or start a new one if the file doesn't exist yet
+    pub fn load(path: PathBuf) -> Result<Ledger> {
+        let mut entries = BTreeMap::new();
+        if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Unable to read {}", path.display()))?;
+            for (i, line) in contents.lines().enumerate().skip(1) {
+                let (key, entry) = parse_line(line)
+                    .with_context(|| format!("{} line {}", path.display(), i + 1))?;
+                entries.insert(key, entry);
+            }
+        }
+        Ok(Ledger { path, entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut contents = String::from(HEADER);
+        contents.push('\n');
+        for ((id, part), entry) in &self.entries {
+            let (answer, ms, run_at) = match &entry.last_run {
+                Some(run) => (
+                    escape(&run.answer),
+                    (run.duration.as_micros() as f64 / 1000.0).to_string(),
+                    run.run_at.to_string(),
+                ),
+                None => Default::default(),
+            };
+            let expected = entry.expected.as_deref().map(escape).unwrap_or_default();
+            contents += &format!("{id}\t{part}\t{expected}\t{answer}\t{ms}\t{run_at}\n");
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, contents)
+            .with_context(|| format!("Unable to write {}", self.path.display()))
+    }
+
+    pub fn get(&self, id: PuzzleId, part: u8) -> Option<&Entry> {
+        self.entries.get(&(id, part))
+    }
+
+    pub fn record(&mut self, result: &PartResult) {
+        let run_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let entry = self.entries.entry((result.id, result.part)).or_default();
+        entry.last_run = Some(LastRun {
+            answer: result.answer.clone(),
+            duration: result.duration,
+            run_at,
+        });
+    }
+
+    /// Mark the last answer as the accepted one. Returns false if the part has never been run.
+    pub fn accept(&mut self, id: PuzzleId, part: u8) -> bool {
+        match self.entries.get_mut(&(id, part)) {
+            Some(entry) if entry.last_run.is_some() => {
+                entry.expected = entry.last_run.as_ref().map(|run| run.answer.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn status(&self, id: PuzzleId, part: u8) -> PartStatus {
+        match self.get(id, part) {
+            Some(Entry {
+                expected: Some(expected),
+                last_run,
+            }) => match last_run {
+                Some(run) if &run.answer != expected => PartStatus::Failing,
+                _ => PartStatus::Solved,
+            },
+            _ => PartStatus::Unsolved,
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<((PuzzleId, u8), Entry)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [puzzle, part, expected, answer, ms, run_at] = fields[..] else {
+        bail!("Expected 6 fields but found {}", fields.len());
+    };
+    let (year, day) = puzzle
+        .split_once('/')
+        .ok_or(anyhow!("Bad puzzle {}", puzzle))?;
+    let id = PuzzleId {
+        year: year.parse()?,
+        day: day.parse()?,
+    };
+    let expected = (!expected.is_empty()).then(|| unescape(expected));
+    let last_run = if answer.is_empty() {
+        None
+    } else {
+        Some(LastRun {
+            answer: unescape(answer),
+            duration: Duration::try_from_secs_f64(ms.parse::<f64>()? / 1000.0)
+                .with_context(|| format!("Bad time {}", ms))?,
+            run_at: run_at.parse()?,
+        })
+    };
+    Ok(((id, part.parse()?), Entry { expected, last_run }))
+}
+
+// Answers may span several lines (e.g. day 10 part 2), so newlines and tabs are escaped
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ledger::{escape, parse_line, unescape, Ledger, PartStatus};
+    use crate::puzzle::{PartResult, PuzzleId};
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn escape_test() {
+        let answer = "\n#..#\n\\#.#\t";
+        assert_eq!(escape(answer), "\\n#..#\\n\\\\#.#\\t");
+        assert_eq!(unescape(&escape(answer)), answer);
+    }
+
+    #[test]
+    fn parse_line_test() {
+        let line = |ms: &str| format!("2022/1\t1\t\t24000\t{}\t1670000000", ms);
+        let (_, entry) = parse_line(&line("1.5")).unwrap();
+        assert_eq!(entry.last_run.unwrap().duration.as_micros(), 1500);
+        for ms in ["-1", "NaN", "inf", "1e300"] {
+            assert!(parse_line(&line(ms)).is_err(), "{}", ms);
+        }
+    }
+
+    #[test]
+    fn bad_time_test() {
+        let dir = env::temp_dir().join(format!("advent2022-bad-time-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let contents = "puzzle\tpart\texpected\tanswer\tms\trun_at\n2022/1\t1\t\t1\t-1\t0\n";
+        fs::write(Ledger::file(&dir), contents).unwrap();
+        let error = format!("{:#}", Ledger::load(Ledger::file(&dir)).err().unwrap());
+        assert!(error.contains("line 2: Bad time -1"), "{}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ledger_test() {
+        let dir = env::temp_dir().join(format!("advent2022-ledger-{}", std::process::id()));
+        let id = PuzzleId {
+            year: 2022,
+            day: 10,
+        };
+        let result = |answer: &str| PartResult {
+            id,
+            title: None,
+            part: 2,
+            answer: answer.to_string(),
+            duration: Duration::from_micros(1500),
+            parse_duration: None,
+            budget: Duration::from_secs(1),
+            allocations: None,
+        };
+
+        let mut ledger = Ledger::load(Ledger::file(&dir)).unwrap();
+        assert_eq!(ledger.status(id, 2), PartStatus::Unsolved);
+        assert!(!ledger.accept(id, 2));
+        ledger.record(&result("\n##..\n"));
+        assert_eq!(ledger.status(id, 2), PartStatus::Unsolved);
+        assert!(ledger.accept(id, 2));
+        assert_eq!(ledger.status(id, 2), PartStatus::Solved);
+        ledger.save().unwrap();
+
+        let mut ledger = Ledger::load(Ledger::file(&dir)).unwrap();
+        let entry = ledger.get(id, 2).unwrap();
+        assert_eq!(entry.expected.as_deref(), Some("\n##..\n"));
+        assert_eq!(entry.last_run.as_ref().unwrap().duration.as_micros(), 1500);
+        ledger.record(&result("\n#...\n"));
+        assert_eq!(ledger.status(id, 2), PartStatus::Failing);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
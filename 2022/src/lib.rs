@@ -0,0 +1,113 @@
+//! Solutions to Advent of Code 2022, and the runner that fetches inputs, solves puzzles and
+//! records their answers.
+//!
+//! The binary is a thin wrapper around [`cli::main`]. Another crate can reuse the runner for puzzles
+//! of its own by registering them alongside (or instead of) the ones in [`PUZZLES_2022`], and then
+//! handing the registry to [`cli::main_with`]:
+//!
+//! ```no_run
+//! use advent2022::cli::Config;
+//! use advent2022::puzzle::{Puzzle, PuzzleFn::USIZE};
+//! use std::path::PathBuf;
+//!
+//! static PUZZLES_2023: [Puzzle; 1] = [Puzzle {
+//!     year: 2023,
+//!     day: 1,
+//!     title: Some("Trebuchet?!"),
+//!     part1: USIZE(|input| Ok(input.lines().count())),
+//!     part2: USIZE(|input| Ok(input.len())),
+//!     ..Puzzle::DEFAULT
+//! }];
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let mut registry = advent2022::registry();
+//!     registry.register(&PUZZLES_2023)?;
+//!     // Inputs are cached under the directory this is run from, and watch rebuilds this crate
+//!     let config = Config {
+//!         watch_crate: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR"))),
+//!         ..Config::default()
+//!     };
+//!     advent2022::cli::main_with(registry, config)
+//! }
+//! ```
+//!
+//! Puzzles should always be defined with `..Puzzle::DEFAULT`, so that they keep compiling as
+//! optional fields are added. The runner, input service and ledger can also be used directly, as can
+//! the helpers in [`util`] and [`parallel`].
+
+// Enum variants in this crate are written in upper case
+#![allow(clippy::upper_case_acronyms)]
+
+extern crate core;
+
+use crate::puzzle::Puzzle;
+use crate::registry::Registry;
+
+pub mod cli;
+mod compare;
+mod day01;
+mod day02;
+mod day03;
+mod day04;
+mod day05;
+mod day06;
+mod day07;
+mod day08;
+mod day09;
+mod day10;
+mod day11;
+mod day12;
+pub mod day13;
+mod day14;
+mod day15;
+mod day16;
+pub mod fuzz;
+pub mod input;
+pub mod ledger;
+mod logging;
+pub mod memory;
+mod page;
+pub mod parallel;
+pub mod puzzle;
+pub mod registry;
+pub mod runner;
+mod sample;
+mod session;
+mod snapshot;
+mod status;
+#[cfg(test)]
+mod testing;
+pub mod util;
+mod verify;
+mod watch;
+
+/// Every puzzle solved in this crate
+pub static PUZZLES_2022: [Puzzle; 16] = [
+    day01::PUZZLE,
+    day02::PUZZLE,
+    day03::PUZZLE,
+    day04::PUZZLE,
+    day05::PUZZLE,
+    day06::PUZZLE,
+    day07::PUZZLE,
+    day08::PUZZLE,
+    day09::PUZZLE,
+    day10::PUZZLE,
+    day11::PUZZLE,
+    day12::PUZZLE,
+    day13::PUZZLE,
+    day14::PUZZLE,
+    day15::PUZZLE,
+    day16::PUZZLE,
+];
+
+/// A registry of the puzzles in this crate. Puzzles from other years can be added by registering
+/// their own list of puzzles.
+pub fn registry() -> Registry {
+    let mut registry = Registry::default();
+    // Each day of 2022 is only listed once
+    registry
+        .register(&PUZZLES_2022)
+        .expect("The 2022 puzzles are all different days");
+    registry
+}
@@ -0,0 +1,84 @@
+use std::io::{self, IsTerminal};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+// Solutions log with tracing, and each part is solved in a span naming the puzzle and part. Logs go
+// to stderr, and nothing below a warning is shown unless asked for with -v (info), -vv (debug) or
+// -vvv (trace), or with directives from RUST_LOG and --log. A crate that calls `main_with` may have
+// installed a subscriber of its own already, and then that one is kept.
+pub(crate) fn init(verbose: u8, directives: &[String]) {
+    let level = match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let mut all = std::env::var("RUST_LOG").unwrap_or_default();
+    for directive in directives {
+        all = all + "," + directive;
+    }
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(filter(level, &all)))
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .try_init()
+        .ok();
+}
+
+// Each day logs under its module path, so `day7=trace` is short for `advent2022::day07=trace`.
+// A bare level replaces the one from -v.
+fn filter(level: LevelFilter, directives: &str) -> String {
+    let mut level = level.to_string();
+    let mut filter = vec![];
+    for directive in directives
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+    {
+        if let Some((day, rest)) = day_directive(directive) {
+            filter.push(format!(
+                "{}::day{:02}{}",
+                env!("CARGO_CRATE_NAME"),
+                day,
+                rest
+            ));
+        } else if directive.parse::<LevelFilter>().is_ok() {
+            level = directive.to_string();
+        } else {
+            filter.push(directive.to_string());
+        }
+    }
+    // The puzzle and part spans are always recorded, so that they show up around anything logged
+    filter.insert(0, format!("{}::puzzle=info", env!("CARGO_CRATE_NAME")));
+    filter.insert(0, level);
+    filter.join(",")
+}
+
+// The day and the rest of a directive like `day7=trace`
+fn day_directive(directive: &str) -> Option<(u32, &str)> {
+    let rest = directive.strip_prefix("day")?;
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    Some((rest[..digits].parse().ok()?, &rest[digits..]))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::logging::filter;
+    use tracing_subscriber::filter::LevelFilter;
+
+    #[test]
+    fn filter_test() {
+        let spans = "advent2022::puzzle=info";
+        assert_eq!(filter(LevelFilter::WARN, ""), format!("warn,{spans}"));
+        assert_eq!(
+            filter(LevelFilter::DEBUG, "day16=trace, day7,reqwest=info"),
+            format!("debug,{spans},advent2022::day16=trace,advent2022::day07,reqwest=info")
+        );
+        assert_eq!(
+            filter(LevelFilter::WARN, "info,days=debug"),
+            format!("info,{spans},days=debug")
+        );
+    }
+}
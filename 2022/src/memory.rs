@@ -0,0 +1,141 @@
+use crate::util::format_bytes;
+#[cfg(feature = "alloc-stats")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+// Whether allocations are being counted. The counts cover every thread, so the runner solves one
+// part at a time when they are.
+pub(crate) const ENABLED: bool = cfg!(feature = "alloc-stats");
+
+// The system allocator, keeping a running count of what has been allocated
+#[cfg(feature = "alloc-stats")]
+struct CountingAllocator;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "alloc-stats")]
+impl CountingAllocator {
+    fn allocated(size: usize) {
+        ALLOCATED.fetch_add(size, Relaxed);
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        let live = LIVE.fetch_add(size, Relaxed) + size;
+        PEAK.fetch_max(live, Relaxed);
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Relaxed);
+    }
+
+    // Counted as a new allocation that replaces the old one, which is what it may well be
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE.fetch_sub(layout.size(), Relaxed);
+            Self::allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// What was allocated while solving a part
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Allocations {
+    pub bytes: usize,
+    pub count: usize,
+    /// The most that was allocated at once, not counting what was already allocated beforehand
+    pub peak: usize,
+}
+
+impl Display for Allocations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} in {} allocations, peak {}",
+            format_bytes(self.bytes),
+            self.count,
+            format_bytes(self.peak)
+        )
+    }
+}
+
+/// Run `f`, counting what it allocates if allocations are being counted. `f` can measure parts of
+/// itself too.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<Allocations>) {
+    if !ENABLED {
+        return (f(), None);
+    }
+    let (allocated, allocations) = (ALLOCATED.load(Relaxed), ALLOCATIONS.load(Relaxed));
+    let live = LIVE.load(Relaxed);
+    let outer_peak = PEAK.swap(live, Relaxed);
+    let value = f();
+    let peak = PEAK.fetch_max(outer_peak, Relaxed);
+    let allocations = Allocations {
+        bytes: ALLOCATED.load(Relaxed) - allocated,
+        count: ALLOCATIONS.load(Relaxed) - allocations,
+        peak: peak.saturating_sub(live),
+    };
+    (value, Some(allocations))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::{measure, Allocations, ENABLED};
+
+    #[test]
+    fn measure_test() {
+        let (sum, allocations) = measure(|| {
+            let numbers: Vec<u64> = (0..1000).collect();
+            numbers.iter().sum::<u64>()
+        });
+        assert_eq!(sum, 499500);
+        if !ENABLED {
+            assert_eq!(allocations, None);
+            return;
+        }
+        // Other tests allocate at the same time, so these are only lower bounds
+        let allocations = allocations.unwrap();
+        assert!(allocations.count >= 1);
+        assert!(allocations.bytes >= 8000);
+        assert!(allocations.peak >= 8000);
+
+        // The peak before a nested measurement still counts
+        let (_, outer) = measure(|| {
+            drop(vec![0u8; 100000]);
+            measure(|| vec![0u8; 10]).1
+        });
+        assert!(outer.unwrap().peak >= 100000);
+    }
+
+    #[test]
+    fn display_test() {
+        let allocations = Allocations {
+            bytes: 3 << 20,
+            count: 12,
+            peak: 1536,
+        };
+        assert_eq!(
+            allocations.to_string(),
+            "3.0 MiB in 12 allocations, peak 1.5 KiB"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+// Helpers for reading puzzle description pages from adventofcode.com
+
+// The puzzle title, from the heading `<h2>--- Day 1: Calorie Counting ---</h2>`
+pub(crate) fn title(html: &str) -> Option<String> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"<h2>--- Day \d+: (.*?) ---</h2>").unwrap();
+    }
+    let caps = RE.captures(html)?;
+    Some(decode_entities(caps.get(1)?.as_str()))
+}
+
+// The contents of every `<pre><code>` block, which is where the example inputs are.
+// Any highlighting inside the block is dropped.
+pub(crate) fn code_blocks(html: &str) -> Vec<String> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+    }
+    RE.captures_iter(html)
+        .map(|caps| decode_entities(&strip_tags(&caps[1])))
+        .collect()
+}
+
+// The answer to the example in each part's description. The description for part 2 is only
+// included when logged in. Each part ends its example with the answer, highlighted like
+// `<code><em>24000</em></code>`, so this takes the last one in each part.
+pub(crate) fn answers(html: &str) -> Vec<String> {
+    lazy_static! {
+        static ref ARTICLE: Regex =
+            Regex::new(r#"(?s)<article class="day-desc">(.*?)</article>"#).unwrap();
+        static ref ANSWER: Regex = Regex::new(r"<code><em>([^<]*)</em></code>").unwrap();
+    }
+    ARTICLE
+        .captures_iter(html)
+        .filter_map(|article| {
+            let answer = ANSWER.captures_iter(&article[1]).last()?;
+            Some(decode_entities(&answer[1]))
+        })
+        .collect()
+}
+
+fn strip_tags(s: &str) -> String {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"<[^>]*>").unwrap();
+    }
+    RE.replace_all(s, "").into_owned()
+}
+
+// Replace the html entities that show up in puzzle pages with the characters they stand for
+pub(crate) fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::page::{answers, code_blocks, decode_entities, title};
+
+    #[test]
+    fn title_test() {
+        let html = r#"<main>
+<article class="day-desc"><h2>--- Day 1: Calorie Counting ---</h2><p>Santa's reindeer</p>"#;
+        assert_eq!(title(html), Some("Calorie Counting".to_string()));
+        let html = "<h2>--- Day 6: Tuning &amp; Trouble ---</h2>";
+        assert_eq!(title(html), Some("Tuning & Trouble".to_string()));
+        assert_eq!(title("<h2>Not a puzzle</h2>"), None);
+    }
+
+    #[test]
+    fn code_blocks_test() {
+        let html =
+            "<p>For example:</p>\n<pre><code>$ cd /\n&lt;dir&gt; <em>a</em>\n</code></pre>\n\
+                    <p>Then <code>a</code></p><pre><code>x\n</code></pre>";
+        assert_eq!(code_blocks(html), vec!["$ cd /\n<dir> a\n", "x\n"]);
+    }
+
+    #[test]
+    fn answers_test() {
+        let html = r#"<article class="day-desc"><h2>--- Day 1: Calorie Counting ---</h2>
+<p>The <code><em>fourth</em></code> Elf is carrying <code><em>24000</em></code> Calories.</p>
+</article><p>Your puzzle answer was <code>68787</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>The sum is <code><em>45000</em></code>.</p></article>"#;
+        assert_eq!(answers(html), vec!["24000", "45000"]);
+        assert!(answers("<p>Not a puzzle</p>").is_empty());
+    }
+
+    #[test]
+    fn decode_entities_test() {
+        assert_eq!(decode_entities("&lt;a&gt; &amp;lt;"), "<a> &lt;");
+    }
+}
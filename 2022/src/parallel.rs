@@ -0,0 +1,69 @@
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Data-parallel helpers for brute force searches within a single puzzle.
+// Results are the same whether or not parallelism is enabled, so it can be turned off to get
+// accurate single threaded benchmarks.
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The maximum of f over all items
+pub fn par_max<I, F, R>(items: I, f: F) -> Option<R>
+where
+    I: IntoParallelIterator + IntoIterator<Item = <I as IntoParallelIterator>::Item>,
+    F: Fn(<I as IntoParallelIterator>::Item) -> R + Sync + Send,
+    R: Ord + Send,
+{
+    if enabled() {
+        items.into_par_iter().map(f).max()
+    } else {
+        items.into_iter().map(f).max()
+    }
+}
+
+/// The first (in iteration order) Some result of f, no matter which thread finds a result first
+pub fn par_find_first<I, F, R>(items: I, f: F) -> Option<R>
+where
+    I: IntoParallelIterator + IntoIterator<Item = <I as IntoParallelIterator>::Item>,
+    F: Fn(<I as IntoParallelIterator>::Item) -> Option<R> + Sync + Send,
+    R: Send,
+{
+    if enabled() {
+        items.into_par_iter().find_map_first(f)
+    } else {
+        items.into_iter().find_map(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parallel::{par_find_first, par_max};
+
+    #[test]
+    fn par_max_test() {
+        let items: Vec<i32> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        assert_eq!(par_max(&items, |i| i * 2), Some(1998));
+        assert_eq!(par_max(0..0, |i| i), None);
+    }
+
+    #[test]
+    fn par_find_first_test() {
+        let first = par_find_first(0..100000, |i| {
+            if i % 997 == 0 && i > 0 {
+                Some(i)
+            } else {
+                None
+            }
+        });
+        assert_eq!(first, Some(997));
+        assert_eq!(par_find_first(0..100, |_| None::<i32>), None);
+    }
+}
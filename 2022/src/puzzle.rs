@@ -1,52 +1,380 @@
-use crate::util::get_input;
-use anyhow::Result;
-use std::time::Instant;
+use crate::memory::{self, Allocations};
+use anyhow::{bail, Context, Result};
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+use tracing::info_span;
+use yansi::Paint;
 
-pub(crate) struct Puzzle {
-    pub(crate) day: i32,
-    pub(crate) part1: PuzzleFn,
-    pub(crate) part2: PuzzleFn,
+pub struct Puzzle {
+    pub year: i32,
+    pub day: i32,
+    pub title: Option<&'static str>,
+    /// Anything worth knowing about the solution, shown by the status command
+    pub notes: Option<&'static str>,
+    pub part1: PuzzleFn,
+    pub part2: PuzzleFn,
+    /// The example input from the puzzle description, for `run --sample`
+    pub sample: Option<&'static str>,
+    /// Puzzles that parse their input once for both parts set this instead of part1 and part2
+    pub parsed: Option<&'static dyn Parsed>,
+    /// Other ways of solving the parts, which the compare command runs alongside the main ones
+    pub variants: &'static [Variant],
+    /// How long each part may take, including any parse stage, if not DEFAULT_BUDGET
+    pub budgets: [Option<Duration>; 2],
 }
 
+/// Any part that takes longer than this is worth another look
+pub const DEFAULT_BUDGET: Duration = Duration::from_secs(1);
+
 impl Puzzle {
-    pub(crate) fn run(&self) -> Result<()> {
-        let input = get_input(self.day)?;
+    /// Defaults for the optional fields, so that a puzzle can be defined with `..Puzzle::DEFAULT`
+    pub const DEFAULT: Puzzle = Puzzle {
+        year: 0,
+        day: 0,
+        title: None,
+        notes: None,
+        part1: PuzzleFn::STR(|_| bail!("Not solved yet")),
+        part2: PuzzleFn::STR(|_| bail!("Not solved yet")),
+        sample: None,
+        parsed: None,
+        variants: &[],
+        budgets: [None, None],
+    };
+
+    pub fn id(&self) -> PuzzleId {
+        PuzzleId {
+            year: self.year,
+            day: self.day,
+        }
+    }
+
+    pub fn part(&self, part: u8) -> &PuzzleFn {
+        match part {
+            1 => &self.part1,
+            2 => &self.part2,
+            _ => panic!("Puzzle {} has no part {}", self.id(), part),
+        }
+    }
+
+    pub fn budget(&self, part: u8) -> Duration {
+        self.budgets[part as usize - 1].unwrap_or(DEFAULT_BUDGET)
+    }
+
+    /// Get the input ready for solving. For puzzles with a parse stage, this parses it and times
+    /// how long that took.
+    pub fn prepare<'a>(&self, input: &'a str) -> Result<Prepared<'a>> {
+        let Some(parsed) = self.parsed else {
+            return Ok(Prepared::Raw(input));
+        };
+        let _span = info_span!("parse", puzzle = %self.id()).entered();
+        let start = Instant::now();
+        let model = parsed.parse(input)?;
+        Ok(Prepared::Parsed(model, start.elapsed()))
+    }
+
+    /// Solve a single part of a prepared input, timing only the solution itself
+    pub fn solve_prepared(&self, part: u8, prepared: &Prepared) -> Result<PartResult> {
+        let _span = info_span!("solve", puzzle = %self.id(), part).entered();
+        let start = Instant::now();
+        let (answer, allocations) = memory::measure(|| match (prepared, self.parsed) {
+            (Prepared::Raw(input), _) => self.part(part).invoke(input),
+            (Prepared::Parsed(model, _), Some(parsed)) => parsed.solve(part, model.as_ref()),
+            (Prepared::Parsed(..), None) => panic!("{} has no parse stage", self.id()),
+        });
+        let duration = start.elapsed();
+        Ok(PartResult {
+            id: self.id(),
+            title: self.title,
+            part,
+            answer: answer.with_context(|| part_name(self.id(), self.title, part))?,
+            duration,
+            parse_duration: match prepared {
+                Prepared::Raw(_) => None,
+                Prepared::Parsed(_, duration) => Some(*duration),
+            },
+            budget: self.budget(part),
+            allocations,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn solve(&self, part: u8, input: &str) -> PartResult {
+        self.solve_prepared(part, &self.prepare(input).unwrap())
+            .unwrap()
+    }
+
+    /// The variants of one part
+    pub fn variants(&self, part: u8) -> impl Iterator<Item = &Variant> {
+        self.variants.iter().filter(move |v| v.part == part)
+    }
+
+    /// Solve a part with one of its variants. Variants always take the raw input, so the time
+    /// includes any parsing.
+    pub fn solve_variant(&self, variant: &Variant, input: &str) -> Result<PartResult> {
+        let _span = info_span!(
+            "solve",
+            puzzle = %self.id(),
+            part = variant.part,
+            variant = variant.name
+        )
+        .entered();
         let start = Instant::now();
-        let answer = self.part1.invoke(&input);
+        let (answer, allocations) = memory::measure(|| variant.solve.invoke(input));
+        Ok(PartResult {
+            id: self.id(),
+            title: self.title,
+            part: variant.part,
+            answer: answer.with_context(|| {
+                format!(
+                    "{} ({})",
+                    part_name(self.id(), self.title, variant.part),
+                    variant.name
+                )
+            })?,
+            duration: start.elapsed(),
+            parse_duration: None,
+            budget: self.budget(variant.part),
+            allocations,
+        })
+    }
+
+    /// Solve a single part, reading the input from `reader`. Parts that stream their input are
+    /// timed including the reading, while other parts read all of the input before starting.
+    pub fn solve_reader(&self, part: u8, reader: &mut dyn BufRead) -> Result<PartResult> {
+        let puzzle_fn = self.part(part);
+        let start = Instant::now();
+        let (answer, allocations) = if let PuzzleFn::STREAM(f) = puzzle_fn {
+            let _span = info_span!("solve", puzzle = %self.id(), part).entered();
+            let (answer, allocations) = memory::measure(|| f(reader));
+            let answer = answer.with_context(|| part_name(self.id(), self.title, part))?;
+            (answer, allocations)
+        } else {
+            let mut input = String::new();
+            reader.read_to_string(&mut input)?;
+            return self.solve_prepared(part, &self.prepare(&input)?);
+        };
         let duration = start.elapsed();
-        println!(
-            "Day {} part 1 ({} ms): {}",
-            self.day,
-            duration.as_micros() as f64 / 1000.0,
+        Ok(PartResult {
+            id: self.id(),
+            title: self.title,
+            part,
             answer,
-        );
-        let start2 = Instant::now();
-        let answer2 = self.part2.invoke(&input);
-        let duration2 = start2.elapsed();
-        println!(
-            "Day {} part 2 ({} ms): {}",
-            self.day,
-            duration2.as_micros() as f64 / 1000.0,
-            answer2,
-        );
-        Ok(())
+            duration,
+            parse_duration: None,
+            budget: self.budget(part),
+            allocations,
+        })
     }
 }
 
-pub(crate) enum PuzzleFn {
-    I32(fn(&str) -> i32),
-    U64(fn(&str) -> u64),
-    USIZE(fn(&str) -> usize),
-    STR(fn(&str) -> String),
+/// An input that is ready to solve
+pub enum Prepared<'a> {
+    Raw(&'a str),
+    // The model shared by both parts, and how long it took to parse
+    Parsed(Model, Duration),
+}
+
+/// The typed model of an input, as produced by a parse stage
+pub type Model = Box<dyn Any + Send + Sync>;
+
+/// A parse stage, with parts that take its model rather than the raw input
+pub trait Parsed: Sync {
+    fn parse(&self, input: &str) -> Result<Model>;
+    fn solve(&self, part: u8, model: &(dyn Any + Send + Sync)) -> Result<String>;
+}
+
+/// Parses the input into an `M`, which both parts then borrow
+pub struct ParsedFns<M, A, B> {
+    pub parse: fn(&str) -> Result<M>,
+    pub part1: fn(&M) -> Result<A>,
+    pub part2: fn(&M) -> Result<B>,
+}
+
+impl<M: Send + Sync + 'static, A: Display, B: Display> Parsed for ParsedFns<M, A, B> {
+    fn parse(&self, input: &str) -> Result<Model> {
+        Ok(Box::new((self.parse)(input)?))
+    }
+
+    fn solve(&self, part: u8, model: &(dyn Any + Send + Sync)) -> Result<String> {
+        let model = model
+            .downcast_ref::<M>()
+            .expect("The model comes from a different puzzle");
+        match part {
+            1 => Ok((self.part1)(model)?.to_string()),
+            2 => Ok((self.part2)(model)?.to_string()),
+            _ => panic!("There is no part {}", part),
+        }
+    }
+}
+
+/// Another solution to one of the parts, e.g. a naive one kept to check a faster one against
+pub struct Variant {
+    pub name: &'static str,
+    pub part: u8,
+    pub solve: PuzzleFn,
+}
+
+pub const PARTS: [u8; 2] = [1, 2];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PuzzleId {
+    pub year: i32,
+    pub day: i32,
+}
+
+impl Display for PuzzleId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.year, self.day)
+    }
+}
+
+#[derive(Debug)]
+pub struct PartResult {
+    pub id: PuzzleId,
+    pub title: Option<&'static str>,
+    pub part: u8,
+    pub answer: String,
+    pub duration: Duration,
+    /// How long the shared parse stage took, for puzzles that have one
+    pub parse_duration: Option<Duration>,
+    pub budget: Duration,
+    /// What the part allocated, when built with the alloc-stats feature. Doesn't include the
+    /// parse stage.
+    pub allocations: Option<Allocations>,
+}
+
+impl PartResult {
+    /// Whether solving the part, including parsing the input, took longer than the budget allows
+    pub fn over_budget(&self) -> bool {
+        self.duration + self.parse_duration.unwrap_or_default() > self.budget
+    }
+}
+
+// How a part is named in results and errors, e.g. "2022 day 1: Calorie Counting, part 2"
+pub(crate) fn part_name(id: PuzzleId, title: Option<&str>, part: u8) -> String {
+    match title {
+        Some(title) => format!("{} day {}: {}, part {}", id.year, id.day, title, part),
+        None => format!("{} day {} part {}", id.year, id.day, part),
+    }
+}
+
+impl Display for PartResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} ms",
+            part_name(self.id, self.title, self.part),
+            self.duration.as_micros() as f64 / 1000.0
+        )?;
+        if let Some(parse_duration) = self.parse_duration {
+            write!(
+                f,
+                ", parsed in {} ms",
+                parse_duration.as_micros() as f64 / 1000.0
+            )?;
+        }
+        if let Some(allocations) = self.allocations {
+            write!(f, ", {}", allocations)?;
+        }
+        if self.over_budget() {
+            let over = format!("over the {} ms budget", self.budget.as_millis());
+            write!(f, ", {}", Paint::magenta(over))?;
+        }
+        write!(f, "): {}", self.answer)
+    }
+}
+
+/// A solution to one part. Malformed input is an error rather than a panic.
+pub enum PuzzleFn {
+    I32(fn(&str) -> Result<i32>),
+    U64(fn(&str) -> Result<u64>),
+    USIZE(fn(&str) -> Result<usize>),
+    STR(fn(&str) -> Result<String>),
+    // Reads the input as it goes, in constant memory, so that it can be used on inputs that are
+    // too big to load. Returns the formatted answer.
+    STREAM(fn(&mut dyn BufRead) -> Result<String>),
 }
 
 impl PuzzleFn {
-    fn invoke(&self, input: &str) -> String {
+    fn invoke(&self, input: &str) -> Result<String> {
         match self {
-            PuzzleFn::I32(f) => f(input).to_string(),
-            PuzzleFn::U64(f) => f(input).to_string(),
-            PuzzleFn::USIZE(f) => f(input).to_string(),
+            PuzzleFn::I32(f) => Ok(f(input)?.to_string()),
+            PuzzleFn::U64(f) => Ok(f(input)?.to_string()),
+            PuzzleFn::USIZE(f) => Ok(f(input)?.to_string()),
             PuzzleFn::STR(f) => f(input),
+            PuzzleFn::STREAM(f) => f(&mut input.as_bytes()),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::puzzle::PuzzleFn::USIZE;
+    use crate::puzzle::{ParsedFns, Prepared, Puzzle, DEFAULT_BUDGET, PARTS};
+    use crate::{day01, day08};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn solve_reader_test() {
+        let calories = "1000\n2000\n\n4000\n\n5000\n\n6000\n";
+        let trees = "30373\n25512\n65332\n33549\n35390\n";
+        // Day 1 streams its input and day 8 doesn't
+        for (puzzle, input) in [(day01::PUZZLE, calories), (day08::PUZZLE, trees)] {
+            for part in PARTS {
+                let result = puzzle.solve_reader(part, &mut input.as_bytes()).unwrap();
+                assert_eq!(result.answer, puzzle.solve(part, input).answer);
+            }
+        }
+    }
+
+    static PARSES: AtomicUsize = AtomicUsize::new(0);
+
+    const WORDS: Puzzle = Puzzle {
+        year: 2022,
+        day: 26,
+        parsed: Some(&ParsedFns {
+            parse: |input| {
+                PARSES.fetch_add(1, Ordering::Relaxed);
+                Ok(input.split_whitespace().map(String::from).collect())
+            },
+            part1: |words: &Vec<String>| Ok(words.len()),
+            part2: |words: &Vec<String>| Ok(words.join("-")),
+        }),
+        ..Puzzle::DEFAULT
+    };
+
+    #[test]
+    fn parse_stage_test() {
+        let prepared = WORDS.prepare("a b c").unwrap();
+        assert!(matches!(prepared, Prepared::Parsed(..)));
+        let results = PARTS.map(|part| WORDS.solve_prepared(part, &prepared).unwrap());
+        assert_eq!(PARSES.load(Ordering::Relaxed), 1);
+        assert_eq!(results[0].answer, "3");
+        assert_eq!(results[1].answer, "a-b-c");
+        assert!(results[0].parse_duration.is_some());
+        assert!(results[1].to_string().contains(", parsed in "));
+    }
+
+    #[test]
+    fn budget_test() {
+        const SLOW: Puzzle = Puzzle {
+            part2: USIZE(|input| Ok(input.len())),
+            budgets: [None, Some(Duration::from_millis(5))],
+            ..Puzzle::DEFAULT
+        };
+        assert_eq!(SLOW.budget(1), DEFAULT_BUDGET);
+        assert_eq!(SLOW.budget(2), Duration::from_millis(5));
+
+        // The parse stage counts towards the budget
+        let mut result = SLOW.solve(2, "a b");
+        result.duration = Duration::from_millis(3);
+        result.parse_duration = Some(Duration::from_millis(1));
+        assert!(!result.over_budget());
+        result.parse_duration = Some(Duration::from_millis(3));
+        assert!(result.over_budget());
+        assert!(result.to_string().contains("over the 5 ms budget"));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::puzzle::{Puzzle, PuzzleId};
+use anyhow::{anyhow, bail, Result};
+use std::str::FromStr;
+
+/// All of the puzzles that the runner knows how to solve, across every year
+#[derive(Default)]
+pub struct Registry {
+    puzzles: Vec<&'static Puzzle>,
+}
+
+impl Registry {
+    /// Add the puzzles for a year (or any other collection of puzzles). Nothing is added if any of
+    /// them are already registered, or are listed twice.
+    pub fn register(&mut self, puzzles: &'static [Puzzle]) -> Result<()> {
+        for (i, puzzle) in puzzles.iter().enumerate() {
+            let mut earlier = self.puzzles.iter().copied().chain(&puzzles[..i]);
+            if earlier.any(|p| p.id() == puzzle.id()) {
+                bail!("Puzzle {} is registered twice", puzzle.id());
+            }
+        }
+        self.puzzles.extend(puzzles);
+        self.puzzles.sort_by_key(|p| p.id());
+        Ok(())
+    }
+
+    /// The year that a bare day number refers to
+    pub fn latest_year(&self) -> Option<i32> {
+        self.puzzles.iter().map(|p| p.year).max()
+    }
+
+    /// The puzzles matching any of the selectors, in order. No selectors selects every puzzle.
+    pub fn select(&self, selectors: &[Selector]) -> Result<Vec<&'static Puzzle>> {
+        if selectors.is_empty() {
+            return Ok(self.puzzles.clone());
+        }
+        let latest_year = self.latest_year().ok_or(anyhow!("No puzzles registered"))?;
+        for selector in selectors {
+            if !self
+                .puzzles
+                .iter()
+                .any(|p| selector.matches(p, latest_year))
+            {
+                bail!("No puzzle for {}", selector.text);
+            }
+        }
+        Ok(self
+            .puzzles
+            .iter()
+            .filter(|p| selectors.iter().any(|s| s.matches(p, latest_year)))
+            .copied()
+            .collect())
+    }
+}
+
+/// Selects puzzles on the command line: `12` for day 12 of the latest year, `2019/12` for day 12 of
+/// 2019, or `2019` for every day of 2019.
+#[derive(Clone, Debug)]
+pub struct Selector {
+    text: String,
+    year: Option<i32>,
+    day: Option<i32>,
+}
+
+impl Selector {
+    /// The single puzzle this selects, which needn't be registered yet
+    pub fn id(&self, latest_year: i32) -> Result<PuzzleId> {
+        let day = self
+            .day
+            .ok_or(anyhow!("Expected DAY or YEAR/DAY, but got {}", self.text))?;
+        Ok(PuzzleId {
+            year: self.year.unwrap_or(latest_year),
+            day,
+        })
+    }
+
+    fn matches(&self, puzzle: &Puzzle, latest_year: i32) -> bool {
+        self.year.unwrap_or(latest_year) == puzzle.year
+            && self.day.is_none_or(|day| day == puzzle.day)
+    }
+}
+
+// Years are told apart from days by their size
+const FIRST_YEAR: i32 = 2015;
+
+impl FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let number = |n: &str| {
+            n.parse::<i32>()
+                .map_err(|_| anyhow!("Expected DAY, YEAR/DAY or YEAR, but got {}", s))
+        };
+        let (year, day) = match s.split_once('/') {
+            Some((year, day)) => (Some(number(year)?), Some(number(day)?)),
+            None => match number(s)? {
+                n if n >= FIRST_YEAR => (Some(n), None),
+                n => (None, Some(n)),
+            },
+        };
+        if let Some(day) = day {
+            if !(1..=25).contains(&day) {
+                bail!("Day should be between 1 and 25, but got {}", day);
+            }
+        }
+        Ok(Selector {
+            text: s.to_string(),
+            year,
+            day,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::puzzle::{Puzzle, PuzzleFn::I32};
+    use crate::registry::{Registry, Selector};
+
+    static PUZZLES_2021: [Puzzle; 2] = [
+        Puzzle {
+            year: 2021,
+            day: 1,
+            part1: I32(|_| Ok(1)),
+            part2: I32(|_| Ok(2)),
+            ..Puzzle::DEFAULT
+        },
+        Puzzle {
+            year: 2021,
+            day: 2,
+            part1: I32(|_| Ok(1)),
+            part2: I32(|_| Ok(2)),
+            ..Puzzle::DEFAULT
+        },
+    ];
+
+    static PUZZLES_2022: [Puzzle; 1] = [Puzzle {
+        year: 2022,
+        day: 1,
+        part1: I32(|_| Ok(1)),
+        part2: I32(|_| Ok(2)),
+        ..Puzzle::DEFAULT
+    }];
+
+    fn select(selectors: &[&str]) -> Vec<String> {
+        let mut registry = Registry::default();
+        registry.register(&PUZZLES_2022).unwrap();
+        registry.register(&PUZZLES_2021).unwrap();
+        let selectors: Vec<Selector> = selectors.iter().map(|s| s.parse().unwrap()).collect();
+        let puzzles = registry.select(&selectors).unwrap();
+        puzzles.iter().map(|p| p.id().to_string()).collect()
+    }
+
+    #[test]
+    fn select_test() {
+        assert_eq!(select(&[]), vec!["2021/1", "2021/2", "2022/1"]);
+        assert_eq!(select(&["1"]), vec!["2022/1"]);
+        assert_eq!(select(&["2022/1", "2021/2"]), vec!["2021/2", "2022/1"]);
+        assert_eq!(select(&["2021"]), vec!["2021/1", "2021/2"]);
+    }
+
+    #[test]
+    fn register_twice_test() {
+        let mut registry = Registry::default();
+        registry.register(&PUZZLES_2022).unwrap();
+        assert!(registry.register(&PUZZLES_2022).is_err());
+        static TWICE: [Puzzle; 2] = [
+            Puzzle {
+                year: 2021,
+                day: 3,
+                ..Puzzle::DEFAULT
+            },
+            Puzzle {
+                year: 2021,
+                day: 3,
+                ..Puzzle::DEFAULT
+            },
+        ];
+        assert!(registry.register(&TWICE).is_err());
+        assert_eq!(registry.select(&[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn selector_parse_test() {
+        assert!("26".parse::<Selector>().is_err());
+        assert!("2022/0".parse::<Selector>().is_err());
+        assert!("day1".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn selector_id_test() {
+        let id = |s: &str| {
+            s.parse::<Selector>()
+                .unwrap()
+                .id(2022)
+                .map(|id| id.to_string())
+        };
+        assert_eq!(id("17").unwrap(), "2022/17");
+        assert_eq!(id("2019/3").unwrap(), "2019/3");
+        assert!(id("2019").is_err());
+    }
+}
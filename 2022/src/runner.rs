@@ -0,0 +1,227 @@
+use crate::memory;
+use crate::puzzle::{part_name, PartResult, Prepared, Puzzle, PARTS};
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::thread;
+use yansi::Paint;
+
+// A single part of a single puzzle, along with the input it should be solved against
+struct Job<'a> {
+    puzzle: &'a Puzzle,
+    part: u8,
+    input: &'a Result<Prepared<'a>>,
+}
+
+impl Job<'_> {
+    fn solve(&self) -> Result<PartResult> {
+        let name = || part_name(self.puzzle.id(), self.puzzle.title, self.part);
+        match self.input {
+            // A part that panics on an odd input, e.g. one passed with --input, shouldn't stop the
+            // others
+            Ok(input) => panic::catch_unwind(AssertUnwindSafe(|| {
+                self.puzzle.solve_prepared(self.part, input)
+            }))
+            .unwrap_or_else(|payload| Err(anyhow!("{}: {}", name(), panic_message(payload)))),
+            // Both parts fail when the input can't be parsed
+            Err(e) => Err(anyhow!("{}: {:#}", name(), e)),
+        }
+    }
+}
+
+// An input that couldn't be read is an error for both parts
+fn read_and_prepare<'a>(puzzle: &Puzzle, input: &'a Result<String>) -> Result<Prepared<'a>> {
+    match input {
+        Ok(input) => prepare(puzzle, input),
+        Err(e) => Err(anyhow!("{:#}", e)),
+    }
+}
+
+// Prepare a puzzle's input, turning a parse stage that panics into an error for that puzzle alone
+pub(crate) fn prepare<'a>(puzzle: &Puzzle, input: &'a str) -> Result<Prepared<'a>> {
+    panic::catch_unwind(AssertUnwindSafe(|| puzzle.prepare(input)))
+        .unwrap_or_else(|payload| Err(anyhow!(panic_message(payload))))
+}
+
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown cause");
+    format!("panicked: {}", message)
+}
+
+/// Solve every part of the given puzzles against their inputs, and print the results in the order
+/// given. Unless `serial` is set, the parts are solved concurrently on a thread pool. Serial mode is
+/// slower overall, but gives more accurate timings since the parts aren't competing with each other
+/// for cpu and memory bandwidth.
+/// Returns the results, in the same order, so that they can be recorded. A part whose input couldn't
+/// be read, parsed or solved is an error, which is printed in its place without stopping the others.
+pub fn run(
+    puzzles: &[&Puzzle],
+    inputs: &[Result<String>],
+    serial: bool,
+) -> Vec<Result<PartResult>> {
+    // Allocations are counted for the whole process, so they can only be told apart one at a time
+    let serial = serial || memory::ENABLED;
+    // Puzzles with a parse stage parse their input once, before either part is solved
+    let inputs: Vec<Result<Prepared>> = if serial {
+        puzzles
+            .iter()
+            .zip(inputs)
+            .map(|(puzzle, input)| read_and_prepare(puzzle, input))
+            .collect()
+    } else {
+        puzzles
+            .par_iter()
+            .zip(inputs)
+            .map(|(puzzle, input)| read_and_prepare(puzzle, input))
+            .collect()
+    };
+
+    let jobs: Vec<Job> = puzzles
+        .iter()
+        .zip(&inputs)
+        .flat_map(|(puzzle, input)| {
+            PARTS.map(|part| Job {
+                puzzle,
+                part,
+                input,
+            })
+        })
+        .collect();
+
+    if serial {
+        jobs.iter()
+            .map(|job| {
+                let result = job.solve();
+                print_result(&result);
+                result
+            })
+            .collect()
+    } else {
+        let (tx, rx) = mpsc::channel();
+        thread::scope(|s| {
+            s.spawn(|| {
+                jobs.par_iter()
+                    .enumerate()
+                    .for_each_with(tx, |tx, (i, job)| tx.send((i, job.solve())).unwrap());
+            });
+            print_in_order(rx)
+        })
+    }
+}
+
+fn print_result(result: &Result<PartResult>) {
+    match result {
+        Ok(result) => println!("{}", result),
+        Err(e) => println!("{}", Paint::red(format!("{:#}", e))),
+    }
+}
+
+// Print results as soon as they and all of the results before them have arrived
+fn print_in_order(rx: mpsc::Receiver<(usize, Result<PartResult>)>) -> Vec<Result<PartResult>> {
+    let mut pending = BTreeMap::new();
+    let mut results = Vec::new();
+    for (i, result) in rx {
+        pending.insert(i, result);
+        while let Some(result) = pending.remove(&results.len()) {
+            print_result(&result);
+            results.push(result);
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use crate::day13;
+    use crate::puzzle::PuzzleFn::USIZE;
+    use crate::puzzle::{ParsedFns, Puzzle};
+    use crate::runner::run;
+    use anyhow::{anyhow, bail};
+
+    const NUMBERS: Puzzle = Puzzle {
+        year: 2022,
+        day: 26,
+        parsed: Some(&ParsedFns {
+            parse: |input| Ok(input.parse::<i32>()?),
+            part1: |n: &i32| Ok(n + 1),
+            part2: |n: &i32| match n {
+                0 => bail!("Can't divide by zero"),
+                n => Ok(100 / n),
+            },
+        }),
+        ..Puzzle::DEFAULT
+    };
+
+    #[test]
+    fn errors_test() {
+        let puzzles = [&NUMBERS, &NUMBERS, &day13::PUZZLE, &NUMBERS];
+        let inputs = [
+            Ok("x".to_string()),
+            Ok("0".to_string()),
+            Ok("[1]\n[2".to_string()),
+            Err(anyhow!("Unable to fetch input for 2022/26")),
+        ];
+        for serial in [false, true] {
+            let answers: Vec<String> = run(&puzzles, &inputs, serial)
+                .into_iter()
+                .map(|result| match result {
+                    Ok(result) => result.answer,
+                    Err(e) => format!("{:#}", e),
+                })
+                .collect();
+            assert_eq!(
+                answers[0],
+                "2022 day 26 part 1: invalid digit found in string"
+            );
+            assert_eq!(
+                answers[1],
+                "2022 day 26 part 2: invalid digit found in string"
+            );
+            assert_eq!(answers[2], "1");
+            assert_eq!(answers[3], "2022 day 26 part 2: Can't divide by zero");
+            assert!(answers[4].starts_with("2022 day 13: Distress Signal, part 1: "));
+            assert_eq!(
+                answers[7],
+                "2022 day 26 part 2: Unable to fetch input for 2022/26"
+            );
+        }
+    }
+
+    #[test]
+    fn panic_test() {
+        const PANICS: Puzzle = Puzzle {
+            year: 2022,
+            day: 27,
+            part1: USIZE(|input| Ok(input.len())),
+            part2: USIZE(|input| Ok(input.as_bytes()[10] as usize)),
+            ..Puzzle::DEFAULT
+        };
+        const PARSE_PANICS: Puzzle = Puzzle {
+            year: 2022,
+            day: 28,
+            parsed: Some(&ParsedFns {
+                parse: |input| Ok(input.as_bytes()[10]),
+                part1: |b: &u8| Ok(*b),
+                part2: |b: &u8| Ok(*b),
+            }),
+            ..Puzzle::DEFAULT
+        };
+        let results = run(
+            &[&PANICS, &PARSE_PANICS],
+            &[Ok("abc".to_string()), Ok("abc".to_string())],
+            false,
+        );
+        assert_eq!(results[0].as_ref().unwrap().answer, "3");
+        let error = results[1].as_ref().unwrap_err().to_string();
+        assert!(error.starts_with("2022 day 27 part 2: panicked: index out of bounds"));
+        let error = results[3].as_ref().unwrap_err().to_string();
+        assert!(error.starts_with("2022 day 28 part 2: panicked: index out of bounds"));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::page;
+use crate::puzzle::PuzzleId;
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The example input from a puzzle description, along with the example answer to each part
+#[derive(Debug, PartialEq)]
+pub(crate) struct Sample {
+    pub(crate) input: String,
+    pub(crate) answers: Vec<String>,
+}
+
+impl Sample {
+    // Pull the sample out of a puzzle page. The first code block is almost always the example
+    // input, but some days have more than one example, so check the result before trusting it.
+    pub(crate) fn from_page(html: &str) -> Result<Sample> {
+        let input = page::code_blocks(html)
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("No example input found in the puzzle page"))?;
+        Ok(Sample {
+            input,
+            answers: page::answers(html),
+        })
+    }
+
+    // Write the input to `<dir>/<year>/sampleNN.txt` and the answers, one per line, to
+    // `<dir>/<year>/sampleNN-answers.txt`
+    pub(crate) fn write_fixtures(&self, dir: &Path, id: PuzzleId) -> Result<Vec<PathBuf>> {
+        let dir = dir.join(id.year.to_string());
+        fs::create_dir_all(&dir)?;
+        let input_file = dir.join(format!("sample{:02}.txt", id.day));
+        let answers_file = dir.join(format!("sample{:02}-answers.txt", id.day));
+        fs::write(&input_file, &self.input)?;
+        fs::write(&answers_file, self.answers.join("\n") + "\n")?;
+        Ok(vec![input_file, answers_file])
+    }
+
+    // The sample and a test module for the day, in the same shape as the ones in template.rs
+    pub(crate) fn test_stub(&self, id: PuzzleId) -> String {
+        let mut stub = format!(
+            "// The example from the puzzle description\nconst SAMPLE_INPUT: &str = \"{}\";\n\n",
+            escape(self.input.trim_end_matches('\n'))
+        );
+        stub += &format!(
+            "#[cfg(test)]\nmod test {{\n    use crate::day{:02}::{{PUZZLE, SAMPLE_INPUT}};\n    use crate::testing::puzzle_tests;\n\n    puzzle_tests! {{\n        PUZZLE;\n",
+            id.day
+        );
+        for (i, answer) in self.answers.iter().enumerate() {
+            stub += &format!(
+                "        part{0}_test: {0}, SAMPLE_INPUT => {1};\n",
+                i + 1,
+                literal(answer)
+            );
+        }
+        stub += "    }\n}\n";
+        stub
+    }
+}
+
+// Numbers are written as they are (with a suffix if they don't fit in an i32), anything else
+// becomes a string
+fn literal(answer: &str) -> String {
+    if answer.parse::<i32>().is_ok() {
+        answer.to_string()
+    } else if answer.parse::<u64>().is_ok() {
+        format!("{}u64", answer)
+    } else {
+        format!("\"{}\"", escape(answer))
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::puzzle::PuzzleId;
+    use crate::sample::Sample;
+    use std::env;
+    use std::fs;
+
+    const PAGE: &str = r#"<main>
+<article class="day-desc"><h2>--- Day 5: Supply Stacks ---</h2>
+<p>For example:</p>
+<pre><code>    [D]
+[N] [C]
+ 1   2
+
+move 1 from 2 to 1
+</code></pre>
+<p>The crates on top are <code><em>CMZ</em></code>.</p>
+</article>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>In this example, <code><em>MCD</em></code> is on top.</p>
+</article>
+</main>"#;
+
+    #[test]
+    fn from_page_test() {
+        let sample = Sample::from_page(PAGE).unwrap();
+        assert_eq!(
+            sample.input,
+            "    [D]\n[N] [C]\n 1   2\n\nmove 1 from 2 to 1\n"
+        );
+        assert_eq!(sample.answers, vec!["CMZ", "MCD"]);
+        assert!(Sample::from_page("<p>Nothing here</p>").is_err());
+    }
+
+    #[test]
+    fn test_stub_test() {
+        let sample = Sample {
+            input: "1\n\\2\n".to_string(),
+            answers: vec!["24000".to_string(), "2713310158".to_string()],
+        };
+        let id = PuzzleId { year: 2022, day: 1 };
+        let stub = sample.test_stub(id);
+        assert!(stub.starts_with("// The example from the puzzle description\nconst SAMPLE_INPUT: &str = \"1\n\\\\2\";\n\n#[cfg(test)]"));
+        assert!(stub.contains("use crate::day01::{PUZZLE, SAMPLE_INPUT};"));
+        assert!(stub.contains("        PUZZLE;\n        part1_test: 1, SAMPLE_INPUT => 24000;\n"));
+        assert!(stub.ends_with("        part2_test: 2, SAMPLE_INPUT => 2713310158u64;\n    }\n}\n"));
+    }
+
+    #[test]
+    fn write_fixtures_test() {
+        let dir = env::temp_dir().join(format!("advent2022-sample-{}", std::process::id()));
+        let page_file = dir.join("puzzle05.html");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&page_file, PAGE).unwrap();
+
+        let sample = Sample::from_page(&fs::read_to_string(&page_file).unwrap()).unwrap();
+        let id = PuzzleId { year: 2022, day: 5 };
+        let files = sample.write_fixtures(&dir, id).unwrap();
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), sample.input);
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "CMZ\nMCD\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
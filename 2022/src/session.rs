@@ -0,0 +1,144 @@
+use anyhow::{bail, Context, Result};
+use std::env;
+use std::fmt::{Debug, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The adventofcode.com session token, as found in the `session` cookie after logging in.
+// The token is never printed.
+pub(crate) struct Session {
+    token: String,
+    pub(crate) source: Source,
+}
+
+pub(crate) enum Source {
+    Flag,
+    Env,
+    ConfigFile(PathBuf),
+}
+
+const SESSION_ENV_VAR: &str = "AOC_SESSION";
+
+impl Session {
+    // Find the session token. In order of precedence, it comes from the --session flag,
+    // the AOC_SESSION environment variable, or the session config file.
+    pub(crate) fn find(flag: Option<&str>) -> Result<Session> {
+        let (token, source) = if let Some(token) = flag {
+            (token.to_string(), Source::Flag)
+        } else if let Ok(token) = env::var(SESSION_ENV_VAR) {
+            (token, Source::Env)
+        } else if let Some(path) = config_file().filter(|path| path.exists()) {
+            warn_if_readable_by_others(&path);
+            let token = fs::read_to_string(&path)
+                .with_context(|| format!("Unable to read {}", path.display()))?;
+            (token, Source::ConfigFile(path))
+        } else {
+            let config_file = config_file().unwrap_or_default();
+            bail!(
+                "No session token found. Set ${} or write it to {}",
+                SESSION_ENV_VAR,
+                config_file.display()
+            );
+        };
+        let token =
+            validate(&token).with_context(|| format!("Bad session token from {}", source))?;
+        Ok(Session { token, source })
+    }
+
+    // The value of the Cookie header to send with requests
+    pub(crate) fn cookie(&self) -> String {
+        format!("session={}", self.token)
+    }
+}
+
+impl Debug for Session {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Session {{ token: <redacted>, source: {} }}",
+            self.source
+        )
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Flag => write!(f, "--session"),
+            Source::Env => write!(f, "${}", SESSION_ENV_VAR),
+            Source::ConfigFile(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME/advent/session, falling back to ~/.config/advent/session
+pub(crate) fn config_file() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("advent").join("session"))
+}
+
+#[cfg(unix)]
+fn warn_if_readable_by_others(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.permissions().mode() & 0o077 != 0 {
+            eprintln!(
+                "Warning: {} is readable by other users. Consider running `chmod 600` on it",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_readable_by_others(_path: &Path) {}
+
+// Session tokens are 128 hex digits (older ones were 96). For convenience, we also accept the whole
+// `session=...` cookie as copied from the browser.
+fn validate(token: &str) -> Result<String> {
+    let token = token.trim();
+    let token = token.strip_prefix("session=").unwrap_or(token);
+    if !token.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Session token should only contain hex digits");
+    }
+    if token.len() != 128 && token.len() != 96 {
+        bail!(
+            "Session token should be 128 hex digits long, but is {}",
+            token.len()
+        );
+    }
+    Ok(token.to_string())
+}
+
+// adventofcode.com responds with a 400 and a request to log in when the session is missing or has
+// expired
+pub(crate) fn is_login_error(status: u16, body: &str) -> bool {
+    status == 400 || body.contains("Please log in")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::session::{is_login_error, validate};
+
+    #[test]
+    fn validate_test() {
+        let token = "0123456789abcdef".repeat(8);
+        assert_eq!(validate(&token).unwrap(), token);
+        assert_eq!(validate(&format!("session={}\n", token)).unwrap(), token);
+        assert!(validate(&token[..96]).is_ok());
+        assert!(validate(&token[..100]).is_err());
+        assert!(validate(&token.replace('a', "g")).is_err());
+        assert!(validate("").is_err());
+    }
+
+    #[test]
+    fn login_error_test() {
+        let body = "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n";
+        assert!(is_login_error(400, body));
+        assert!(is_login_error(200, body));
+        assert!(!is_login_error(200, "1000\n2000\n"));
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use itertools::{EitherOrBoth, Itertools};
+#[cfg(test)]
+use lazy_static::lazy_static;
+#[cfg(test)]
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use yansi::Paint;
+
+// Snapshot tests compare a render with the expected one in snapshots/<name>.snap. When they differ,
+// or there is no expected render yet, the test writes what it got to <name>.snap.new so that it
+// can be reviewed with the snapshots command.
+pub(crate) const SNAPSHOT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots");
+
+// Whether a snapshot keeps the ANSI colour codes in a render
+#[cfg(test)]
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Colour {
+    Strip,
+    Keep,
+}
+
+#[cfg(test)]
+pub(crate) fn strip_ansi(s: &str) -> String {
+    lazy_static! {
+        static ref RE: Regex = Regex::new("\x1b\\[[0-9;]*m").unwrap();
+    }
+    RE.replace_all(s, "").to_string()
+}
+
+// A render that doesn't match its snapshot, waiting to be accepted or rejected
+pub(crate) struct Pending {
+    pub(crate) name: String,
+    // None for a new snapshot
+    pub(crate) expected: Option<String>,
+    pub(crate) actual: String,
+    path: PathBuf,
+}
+
+impl Pending {
+    pub(crate) fn accept(&self) -> Result<()> {
+        fs::rename(new_path(&self.path), &self.path)
+            .with_context(|| format!("Unable to accept {}", self.name))
+    }
+
+    pub(crate) fn reject(&self) -> Result<()> {
+        fs::remove_file(new_path(&self.path))
+            .with_context(|| format!("Unable to reject {}", self.name))
+    }
+}
+
+fn new_path(path: &Path) -> PathBuf {
+    path.with_extension("snap.new")
+}
+
+// The renders in `dir` that are waiting for review, in name order
+pub(crate) fn pending(dir: &Path) -> Result<Vec<Pending>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut pending = vec![];
+    for entry in fs::read_dir(dir)? {
+        let new = entry?.path();
+        let Some(name) = new.to_str().and_then(|p| p.strip_suffix(".snap.new")) else {
+            continue;
+        };
+        let path = PathBuf::from(format!("{}.snap", name));
+        pending.push(Pending {
+            name: path.file_stem().unwrap().to_string_lossy().to_string(),
+            expected: fs::read_to_string(&path).ok(),
+            actual: fs::read_to_string(&new)?,
+            path,
+        });
+    }
+    pending.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(pending)
+}
+
+// The lines that differ, marked with - for the expected render and + for the actual one. Renders
+// are grids, so lines are compared by position.
+pub(crate) fn diff(expected: &str, actual: &str) -> String {
+    let mut diff = String::new();
+    for lines in expected.lines().zip_longest(actual.lines()) {
+        match lines {
+            EitherOrBoth::Both(a, b) if a == b => diff += &format!("  {}\n", a),
+            EitherOrBoth::Both(a, b) => {
+                diff += &format!("{} {}\n", Paint::red('-'), a);
+                diff += &format!("{} {}\n", Paint::green('+'), b);
+            }
+            EitherOrBoth::Left(a) => diff += &format!("{} {}\n", Paint::red('-'), a),
+            EitherOrBoth::Right(b) => diff += &format!("{} {}\n", Paint::green('+'), b),
+        }
+    }
+    diff
+}
+
+// Show each pending render, and then accept or reject them all if asked to
+pub(crate) fn review(dir: &Path, accept: bool, reject: bool) -> Result<()> {
+    let pending = pending(dir)?;
+    if pending.is_empty() {
+        println!("No snapshots to review");
+        return Ok(());
+    }
+    for snapshot in &pending {
+        match &snapshot.expected {
+            Some(expected) => {
+                println!("{} changed:", Paint::new(&snapshot.name).bold());
+                print!("{}", diff(expected, &snapshot.actual));
+            }
+            None => {
+                println!("{} is new:", Paint::new(&snapshot.name).bold());
+                print!("{}", snapshot.actual);
+            }
+        }
+        println!();
+        if accept {
+            snapshot.accept()?;
+        } else if reject {
+            snapshot.reject()?;
+        }
+    }
+    match (accept, reject) {
+        (true, _) => println!("Accepted {} snapshots", pending.len()),
+        (_, true) => println!("Rejected {} snapshots", pending.len()),
+        _ => println!("Run with --accept or --reject to update them"),
+    }
+    Ok(())
+}
+
+// Check a render against its snapshot, failing the test if it doesn't match. Set UPDATE_SNAPSHOTS
+// to write the render as the expected one instead.
+#[cfg(test)]
+pub(crate) fn check_snapshot(name: &str, render: &str, colour: Colour) {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    if let Err(message) = check_in(Path::new(SNAPSHOT_DIR), name, render, colour, update) {
+        panic!("{}", message);
+    }
+}
+
+#[cfg(test)]
+fn check_in(
+    dir: &Path,
+    name: &str,
+    render: &str,
+    colour: Colour,
+    update: bool,
+) -> std::result::Result<(), String> {
+    let render = match colour {
+        Colour::Strip => strip_ansi(render),
+        Colour::Keep => render.to_string(),
+    };
+    let path = dir.join(format!("{}.snap", name));
+    fs::create_dir_all(dir).unwrap();
+    if update {
+        fs::write(&path, render).unwrap();
+        return Ok(());
+    }
+    let expected = fs::read_to_string(&path).ok();
+    if expected.as_ref() == Some(&render) {
+        // An earlier failure is out of date
+        let _ = fs::remove_file(new_path(&path));
+        return Ok(());
+    }
+    fs::write(new_path(&path), &render).unwrap();
+    Err(match expected {
+        Some(expected) => format!(
+            "The {} snapshot doesn't match. Run `cargo run -- snapshots` to review it\n{}",
+            name,
+            diff(&expected, &render)
+        ),
+        None => format!(
+            "There is no {} snapshot yet. Run `cargo run -- snapshots` to review it",
+            name
+        ),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::snapshot::{check_in, diff, pending, strip_ansi, Colour};
+    use std::env;
+    use std::fs;
+    use yansi::Paint;
+
+    #[test]
+    fn strip_ansi_test() {
+        let render = format!("{}.{}", Paint::rgb(1, 2, 3, '#'), Paint::red('o').bold());
+        assert_eq!(strip_ansi(&render), "#.o");
+    }
+
+    #[test]
+    fn diff_test() {
+        let diff = strip_ansi(&diff("a\nb\nc", "a\nx\nc\nd"));
+        assert_eq!(diff, "  a\n- b\n+ x\n  c\n+ d\n");
+    }
+
+    #[test]
+    fn review_test() {
+        let dir = env::temp_dir().join(format!("advent2022-snapshot-{}", std::process::id()));
+        let check = |render: &str| check_in(&dir, "cave", render, Colour::Strip, false);
+
+        // A new snapshot fails until it is accepted
+        let render = format!("#{}\n##\n", Paint::red('o'));
+        assert!(check(&render).is_err());
+        let [new] = &pending(&dir).unwrap()[..] else {
+            panic!("Expected one pending snapshot");
+        };
+        assert_eq!((new.name.as_str(), new.expected.as_ref()), ("cave", None));
+        assert_eq!(new.actual, "#o\n##\n");
+        new.accept().unwrap();
+        assert!(check(&render).is_ok());
+        assert!(pending(&dir).unwrap().is_empty());
+
+        // A changed render fails until it is accepted, and can be rejected
+        let message = check("#.\n##\n").unwrap_err();
+        assert!(strip_ansi(&message).ends_with("- #o\n+ #.\n  ##\n"));
+        pending(&dir).unwrap()[0].reject().unwrap();
+        assert!(pending(&dir).unwrap().is_empty());
+        assert!(check(&render).is_ok());
+        assert_eq!(
+            fs::read_to_string(dir.join("cave.snap")).unwrap(),
+            "#o\n##\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use crate::ledger::{Entry, Ledger, PartStatus};
+use crate::puzzle::{Puzzle, PuzzleId, PARTS};
+use crate::util::format_duration;
+use std::collections::BTreeSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use yansi::Paint;
+
+// Print a calendar for each year showing the status of every part, followed by the title, notes,
+// and last run of each puzzle. `titles` holds the title of each puzzle, if it is known.
+pub(crate) fn print_status(puzzles: &[&Puzzle], titles: &[Option<String>], ledger: &Ledger) {
+    let years: BTreeSet<i32> = puzzles.iter().map(|p| p.year).collect();
+    for year in years {
+        println!("{}", Paint::new(year).bold());
+        for week in 0..5 {
+            for day in week * 5 + 1..=week * 5 + 5 {
+                let id = PuzzleId { year, day };
+                print!("{:>4} ", day);
+                if puzzles.iter().any(|p| p.id() == id) {
+                    for part in PARTS {
+                        print!("{}", glyph(ledger.status(id, part)));
+                    }
+                } else {
+                    print!("  ");
+                }
+            }
+            println!();
+        }
+        println!();
+
+        for (puzzle, title) in puzzles.iter().zip(titles) {
+            if puzzle.year != year {
+                continue;
+            }
+            println!("{:>4}  {}", puzzle.day, title.as_deref().unwrap_or("?"));
+            for part in PARTS {
+                let id = puzzle.id();
+                println!(
+                    "      {} part {}: {}",
+                    glyph(ledger.status(id, part)),
+                    part,
+                    describe(ledger.get(id, part))
+                );
+            }
+            if let Some(notes) = puzzle.notes {
+                println!("      {}", Paint::new(notes).italic());
+            }
+        }
+        println!();
+    }
+}
+
+fn glyph(status: PartStatus) -> Paint<char> {
+    match status {
+        PartStatus::Solved => Paint::yellow('*'),
+        PartStatus::Failing => Paint::red('x'),
+        PartStatus::Unsolved => Paint::new('.').dimmed(),
+    }
+}
+
+fn describe(entry: Option<&Entry>) -> String {
+    let Some(run) = entry.and_then(|e| e.last_run.as_ref()) else {
+        return "never run".to_string();
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let ago = Duration::from_secs(now.saturating_sub(run.run_at));
+    let mut description = format!(
+        "{} ({} ms, {} ago)",
+        short(&run.answer),
+        run.duration.as_micros() as f64 / 1000.0,
+        format_duration(ago)
+    );
+    if let Some(expected) = entry.and_then(|e| e.expected.as_ref()) {
+        if expected != &run.answer {
+            description += &format!(", expected {}", short(expected));
+        }
+    }
+    description
+}
+
+// Multi-line answers (pictures of letters) don't fit on a line
+pub(crate) fn short(answer: &str) -> String {
+    if answer.contains('\n') {
+        format!("<{} lines>", answer.trim().lines().count())
+    } else {
+        answer.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ledger::{Entry, LastRun};
+    use crate::status::describe;
+    use std::time::Duration;
+
+    #[test]
+    fn describe_test() {
+        assert_eq!(describe(None), "never run");
+        let entry = Entry {
+            expected: Some("\n##\n##\n".to_string()),
+            last_run: Some(LastRun {
+                answer: "24".to_string(),
+                duration: Duration::from_micros(1500),
+                run_at: 0,
+            }),
+        };
+        let description = describe(Some(&entry));
+        assert!(description.starts_with("24 (1.5 ms, "));
+        assert!(description.ends_with(" ago), expected <2 lines>"));
+    }
+}
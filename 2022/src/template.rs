@@ -1,39 +1,35 @@
 use crate::puzzle::{Puzzle, PuzzleFn::I32};
+use anyhow::Result;
 
 pub(crate) const PUZZLE: Puzzle = Puzzle {
+    year: 2022,
     day: 0,
+    title: None,
     part1: I32(part1),
     part2: I32(part2),
+    sample: Some(SAMPLE_INPUT),
+    ..Puzzle::DEFAULT
 };
 
-fn part1(input: &str) -> i32 {
+fn part1(input: &str) -> Result<i32> {
     todo!()
 }
 
-fn part2(input: &str) -> i32 {
+fn part2(input: &str) -> Result<i32> {
     todo!()
 }
 
+// The example from the puzzle description
+const SAMPLE_INPUT: &str = "";
+
 #[cfg(test)]
 mod test {
-    use crate::template::{part1, part2, PUZZLE};
-
-    #[test]
-    fn part1_test() {
-        let answer = part1(SAMPLE_INPUT);
-        assert_eq!(answer, 0)
-    }
+    use crate::template::{PUZZLE, SAMPLE_INPUT};
+    use crate::testing::puzzle_tests;
 
-    #[test]
-    fn part2_test() {
-        let answer = part2(SAMPLE_INPUT);
-        assert_eq!(answer, 0)
+    puzzle_tests! {
+        PUZZLE;
+        part1_test: 1, SAMPLE_INPUT => 0;
+        part2_test: 2, SAMPLE_INPUT => 0;
     }
-
-    #[test]
-    fn run() {
-        assert!(PUZZLE.run().is_ok())
-    }
-
-    const SAMPLE_INPUT: &str = "";
 }
@@ -0,0 +1,104 @@
+use crate::input::{cached_input, default_cache_dir};
+use crate::ledger::Ledger;
+use crate::puzzle::{Puzzle, PARTS};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+use std::env;
+use std::fmt::{Debug, Display};
+
+// Generates the tests for a day. Each case names a test and gives the part, the input and the
+// expected answer. A `real_input` test is also generated, which checks the answers to the real
+// input against the accepted answers in the ledger.
+//
+//     puzzle_tests! {
+//         PUZZLE;
+//         part1_test: 1, SAMPLE_INPUT => 95437;
+//         part2_test: 2, SAMPLE_INPUT => 24933642;
+//     }
+macro_rules! puzzle_tests {
+    ($puzzle:expr; $($name:ident: $part:literal, $input:expr => $expected:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                $crate::testing::check_answer(&$puzzle, $part, $input, $expected);
+            }
+        )*
+
+        #[test]
+        fn real_input() {
+            $crate::testing::check_real_input(&$puzzle);
+        }
+    };
+}
+
+pub(crate) use puzzle_tests;
+
+pub(crate) fn check_answer(puzzle: &Puzzle, part: u8, input: &str, expected: impl Display) {
+    let result = puzzle.solve(part, input);
+    assert_eq!(result.answer, expected.to_string());
+    for variant in puzzle.variants(part) {
+        let result = puzzle.solve_variant(variant, input).unwrap();
+        assert_eq!(
+            result.answer,
+            expected.to_string(),
+            "variant {}",
+            variant.name
+        );
+    }
+}
+
+// Tests never fetch inputs, so this passes without checking anything when the input isn't cached.
+// Parts without an accepted answer are still solved, to catch panics.
+pub(crate) fn check_real_input(puzzle: &Puzzle) {
+    let cache_dir = default_cache_dir();
+    let Some(input) = cached_input(&cache_dir, puzzle.id()) else {
+        eprintln!("Skipping {}: the input isn't cached", puzzle.id());
+        return;
+    };
+    let ledger = Ledger::load(Ledger::file(&cache_dir)).unwrap();
+    for part in PARTS {
+        let result = puzzle.solve(part, &input);
+        if let Some(expected) = ledger
+            .get(puzzle.id(), part)
+            .and_then(|e| e.expected.as_ref())
+        {
+            assert_eq!(
+                &result.answer,
+                expected,
+                "{} part {} gave a different answer to the accepted one",
+                puzzle.id(),
+                part
+            );
+        }
+    }
+}
+
+// Random inputs per differential check, unless overridden with $PROPTEST_CASES
+const DIFFERENTIAL_CASES: u32 = 1000;
+
+// Check that a simple reference solver and an optimized one agree on every input that `inputs`
+// generates. On failure, the input is shrunk to a minimal example before panicking.
+pub(crate) fn differential<S, T>(
+    inputs: S,
+    reference: impl Fn(&str) -> T,
+    optimized: impl Fn(&str) -> T,
+) where
+    S: Strategy<Value = String>,
+    T: PartialEq + Debug,
+{
+    let cases = env::var("PROPTEST_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(DIFFERENTIAL_CASES);
+    let mut runner = TestRunner::new(Config {
+        cases,
+        ..Config::default()
+    });
+    let result = runner.run(&inputs, |input| {
+        prop_assert_eq!(reference(&input), optimized(&input), "input:\n{}", input);
+        Ok(())
+    });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
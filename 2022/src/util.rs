@@ -1,87 +1,129 @@
-use anyhow::{Error, Result};
+use anyhow::{bail, Result};
 use colorsys::{Hsl, Rgb};
-use std::fs;
+use nom::IResult;
+use std::io::BufRead;
+use std::time::Duration;
 
-pub(crate) fn get_input(day: i32) -> Result<String> {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(read_or_fetch_input(day))
-}
-
-const YEAR: &str = "2022";
-
-// #[tokio::main]
-async fn read_or_fetch_input(day: i32) -> Result<String> {
-    let input = read_input_from_file(day);
-    if input.is_ok() {
-        return input;
+/// Maps an integer onto a rbg rainbow color gradient
+pub fn color_gradient(x: i32, lightness: Option<f64>) -> (u8, u8, u8) {
+    if x == i32::MAX {
+        (255, 255, 255)
+    } else if x < 0 {
+        (0, 0, 0)
+    } else {
+        let hue = (x % 360).into();
+        let hsl = Hsl::new(hue, 100.0, lightness.unwrap_or(50.0), None);
+        let rgb = Rgb::from(hsl);
+        (rgb.red() as u8, rgb.green() as u8, rgb.blue() as u8)
     }
-    let input = fetch_input(day).await?;
-    write_input_to_file(day, &input)?;
-    Ok(input)
-}
-
-fn read_input_from_file(day: i32) -> Result<String> {
-    Ok(fs::read_to_string(input_filename(day))?)
 }
 
-fn write_input_to_file(day: i32, input: &str) -> Result<()> {
-    Ok(fs::write(input_filename(day), input)?)
+/// Formats a duration as e.g. 2d 1h 0m, or 1h 2m 5s if it is less than a day
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else {
+        format!("{}h {}m {}s", hours, minutes, seconds % 60)
+    }
 }
 
-fn input_filename(day: i32) -> String {
-    format!("inputs/input{:02}.txt", day)
+/// Formats a number of bytes as e.g. 512 B, 1.5 KiB or 3.0 MiB
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let (mut size, mut unit) = (bytes as f64 / 1024.0, 0);
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
 }
 
-fn input_uri(day: i32) -> String {
-    format!("https://adventofcode.com/{}/day/{}/input", YEAR, day)
+/// Run a nom parser over the whole of `input`, turning a failure or any leftover input into an error
+pub fn parse_all<'a, T>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+    input: &'a str,
+) -> Result<T> {
+    match parser(input) {
+        Ok(("", value)) => Ok(value),
+        Ok((rest, _)) => bail!("Unexpected {:?} at the end of {:?}", rest, input),
+        Err(e) => bail!("Unable to parse {:?}: {}", input, e),
+    }
 }
 
-fn get_cookie() -> Result<String> {
-    let cookie = fs::read_to_string("cookie.txt")?;
-    Ok(match cookie.strip_suffix('\n') {
-        Some(stripped) => stripped.to_string(),
-        None => cookie,
-    })
+/// Reads a line at a time into the same buffer, so that memory use doesn't grow with the input.
+/// Lines are split the same way as `str::lines`.
+pub struct LineReader<'a> {
+    reader: &'a mut dyn BufRead,
+    line: String,
 }
 
-async fn fetch_input(day: i32) -> Result<String> {
-    let client = reqwest::Client::new();
-    let cookie = get_cookie()?;
-    let result = client
-        .get(input_uri(day))
-        .header("Cookie", cookie)
-        .send()
-        .await;
-    if result.is_ok() {
-        let body = result?.text().await?;
-        Ok(body)
-    } else {
-        let err = result.err().unwrap();
-        println!("{}", err);
-        Err(Error::from(err))
+impl<'a> LineReader<'a> {
+    pub fn new(reader: &'a mut dyn BufRead) -> Self {
+        LineReader {
+            reader,
+            line: String::new(),
+        }
     }
-}
 
-// Maps an integer onto a rbg rainbow color gradient
-pub(crate) fn color_gradient(x: i32, lightness: Option<f64>) -> (u8, u8, u8) {
-    if x == i32::MAX {
-        (255, 255, 255)
-    } else if x < 0 {
-        (0, 0, 0)
-    } else {
-        let hue = (x % 360).into();
-        let hsl = Hsl::new(hue, 100.0, lightness.unwrap_or(50.0), None);
-        let rgb = Rgb::from(hsl);
-        (rgb.red() as u8, rgb.green() as u8, rgb.blue() as u8)
+    pub fn next_line(&mut self) -> Result<Option<&str>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        let line = self.line.strip_suffix('\n').unwrap_or(&self.line);
+        Ok(Some(line.strip_suffix('\r').unwrap_or(line)))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::util::color_gradient;
+    use crate::util::{color_gradient, format_bytes, format_duration, parse_all, LineReader};
+    use nom::character::complete::i32 as nom_i32;
+    use std::time::Duration;
 
     #[test]
     fn color_gradient_test() {
         assert_eq!(color_gradient(0, None), (255, 0, 0))
     }
+
+    #[test]
+    fn format_duration_test() {
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1h 2m 5s");
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 86400 + 3600)),
+            "2d 1h 0m"
+        );
+    }
+
+    #[test]
+    fn format_bytes_test() {
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 << 30), "5.0 GiB");
+        assert_eq!(format_bytes(usize::MAX), "16777216.0 TiB");
+    }
+
+    #[test]
+    fn parse_all_test() {
+        assert_eq!(parse_all(nom_i32, "-12").unwrap(), -12);
+        assert!(parse_all(nom_i32, "12 ").is_err());
+        assert!(parse_all(nom_i32, "x").is_err());
+    }
+
+    #[test]
+    fn line_reader_test() {
+        let input = "a\r\n\nb\nc";
+        let mut reader = input.as_bytes();
+        let mut lines = LineReader::new(&mut reader);
+        let mut read = vec![];
+        while let Some(line) = lines.next_line().unwrap() {
+            read.push(line.to_string());
+        }
+        assert_eq!(read, input.lines().collect::<Vec<_>>());
+    }
 }
@@ -0,0 +1,254 @@
+use crate::input::cached_input;
+use crate::ledger::Ledger;
+use crate::puzzle::{Puzzle, PuzzleId, PARTS};
+use crate::runner::{panic_message, prepare};
+use crate::status::short;
+use anyhow::{anyhow, bail, Context, Result};
+use rayon::prelude::*;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use yansi::Paint;
+
+// Each subdirectory of the inputs directory holds one user's inputs (`<user>/<year>/inputNN.txt`,
+// or `<user>/inputNN.txt` for 2022, like the input cache), along with the answers.tsv that records the
+// answers adventofcode.com accepted from them. The cache's own year directories aren't users.
+pub(crate) struct User {
+    pub(crate) name: String,
+    dir: PathBuf,
+}
+
+// The users in an inputs directory, in name order
+pub(crate) fn users(dir: &Path) -> Result<Vec<User>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Unable to read {}", dir.display()))?;
+    let mut users = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if path.is_dir() && name.parse::<i32>().is_err() {
+            users.push(User { name, dir: path });
+        }
+    }
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(users)
+}
+
+impl User {
+    fn input(&self, id: PuzzleId) -> Option<String> {
+        cached_input(&self.dir, id)
+    }
+}
+
+// What happened when one part was solved for one user
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // The answer is the one the user recorded
+    Pass(Duration),
+    Fail {
+        answer: String,
+        expected: String,
+        duration: Duration,
+    },
+    // The user hasn't recorded an answer, so there is nothing to compare with
+    Unchecked {
+        answer: String,
+        duration: Duration,
+    },
+    // The input couldn't be parsed, or the solution panicked
+    Error(String),
+}
+
+// A user's outcome for each part, or None if they don't have an input for the puzzle
+pub(crate) type Row = Option<Vec<Outcome>>;
+
+// Solve a puzzle against every user's input, in parallel
+pub(crate) fn verify_puzzle(puzzle: &Puzzle, users: &[User]) -> Result<Vec<Row>> {
+    users
+        .par_iter()
+        .map(|user| {
+            let Some(input) = user.input(puzzle.id()) else {
+                return Ok(None);
+            };
+            let ledger = Ledger::load(Ledger::file(&user.dir))?;
+            let prepared = match prepare(puzzle, &input) {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    return Ok(Some(Vec::from(
+                        PARTS.map(|_| Outcome::Error(format!("{:#}", e))),
+                    )))
+                }
+            };
+            let outcomes = PARTS.map(|part| {
+                // A solution that panics on one user's input shouldn't stop the others
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    puzzle.solve_prepared(part, &prepared)
+                }));
+                let result = match result {
+                    Ok(Ok(result)) => result,
+                    Ok(Err(e)) => return Outcome::Error(format!("{:#}", e)),
+                    Err(payload) => return Outcome::Error(panic_message(payload)),
+                };
+                let expected = ledger
+                    .get(puzzle.id(), part)
+                    .and_then(|e| e.expected.clone());
+                match expected {
+                    Some(expected) if expected == result.answer => Outcome::Pass(result.duration),
+                    Some(expected) => Outcome::Fail {
+                        answer: result.answer,
+                        expected,
+                        duration: result.duration,
+                    },
+                    None => Outcome::Unchecked {
+                        answer: result.answer,
+                        duration: result.duration,
+                    },
+                }
+            });
+            Ok(Some(Vec::from(outcomes)))
+        })
+        .collect()
+}
+
+// Solve each puzzle against every user's input in `dir`, and print a matrix of the outcomes.
+// It is an error if any answer doesn't match.
+pub(crate) fn verify(puzzles: &[&Puzzle], dir: &Path) -> Result<()> {
+    let users = users(dir)?;
+    if users.is_empty() {
+        bail!("There are no users in {}", dir.display());
+    }
+    let (mut checked, mut failures) = (0, 0);
+    for puzzle in puzzles {
+        let rows = verify_puzzle(puzzle, &users)?;
+        print_matrix(puzzle, &users, &rows);
+        for outcome in rows.iter().flatten().flatten() {
+            match outcome {
+                Outcome::Pass(_) => checked += 1,
+                Outcome::Fail { .. } | Outcome::Error(_) => {
+                    checked += 1;
+                    failures += 1;
+                }
+                Outcome::Unchecked { .. } => {}
+            }
+        }
+    }
+    match failures {
+        0 => Ok(()),
+        _ => Err(anyhow!("{} of {} answers didn't match", failures, checked)),
+    }
+}
+
+fn print_matrix(puzzle: &Puzzle, users: &[User], rows: &[Row]) {
+    print!("{} day {}", puzzle.year, puzzle.day);
+    if let Some(title) = puzzle.title {
+        print!(": {}", title);
+    }
+    println!();
+    let width = users.iter().map(|u| u.name.len()).max().unwrap_or(0).max(4);
+    let header: String = PARTS.map(|part| format!("  part {:<11}", part)).concat();
+    println!("  {:width$}{}", "user", header.trim_end());
+
+    let mut details = vec![];
+    for (user, row) in users.iter().zip(rows) {
+        print!("  {:width$}", user.name);
+        let Some(outcomes) = row else {
+            println!("  {}", Paint::new("no input").dimmed());
+            continue;
+        };
+        for (part, outcome) in PARTS.iter().zip(outcomes) {
+            let (glyph, duration) = match outcome {
+                Outcome::Pass(duration) => (Paint::yellow('*'), Some(duration)),
+                Outcome::Fail { duration, .. } => (Paint::red('x'), Some(duration)),
+                Outcome::Unchecked { duration, .. } => (Paint::new('?').dimmed(), Some(duration)),
+                Outcome::Error(_) => (Paint::red('!'), None),
+            };
+            match duration {
+                Some(duration) => print!(
+                    "  {} {:>10.3} ms",
+                    glyph,
+                    duration.as_micros() as f64 / 1000.0
+                ),
+                None => print!("  {} {:>13}", glyph, ""),
+            }
+            match outcome {
+                Outcome::Fail {
+                    answer, expected, ..
+                } => details.push(format!(
+                    "{} part {}: {}, expected {}",
+                    user.name,
+                    part,
+                    short(answer),
+                    short(expected)
+                )),
+                Outcome::Error(e) => details.push(format!("{} part {}: {}", user.name, part, e)),
+                _ => {}
+            }
+        }
+        println!();
+    }
+    for detail in details {
+        println!("  {}", detail);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::day01;
+    use crate::ledger::Ledger;
+    use crate::puzzle::PuzzleId;
+    use crate::verify::{users, verify_puzzle, Outcome};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn verify_puzzle_test() {
+        let dir = env::temp_dir().join(format!("advent2022-verify-{}", std::process::id()));
+        let id = PuzzleId { year: 2022, day: 1 };
+        // alice's answers are right, bob's part 2 answer is wrong, carol hasn't recorded any
+        // answers, dave's input is broken and erin has no input. 2022 is the cache's own inputs.
+        let inputs = [
+            ("2022", "input01.txt", "1"),
+            ("alice", "2022/input01.txt", "1\n2\n\n4"),
+            ("bob", "input01.txt", "1\n2\n\n4"),
+            ("carol", "input01.txt", "5"),
+            ("dave", "input01.txt", "x"),
+            ("erin", "input02.txt", "A Y"),
+        ];
+        for (user, file, input) in inputs {
+            let path = dir.join(user).join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, input).unwrap();
+        }
+        for (user, answers) in [("alice", "4\t7"), ("bob", "4\t6"), ("dave", "4\t7")] {
+            let mut contents = String::from("puzzle\tpart\texpected\tanswer\tms\trun_at\n");
+            for (part, answer) in answers.split('\t').enumerate() {
+                contents += &format!("{}\t{}\t{}\t\t\t\n", id, part + 1, answer);
+            }
+            fs::write(Ledger::file(&dir.join(user)), contents).unwrap();
+        }
+
+        let users = users(&dir).unwrap();
+        let names: Vec<&str> = users.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["alice", "bob", "carol", "dave", "erin"]);
+        let rows = verify_puzzle(&day01::PUZZLE, &users).unwrap();
+        let alice = rows[0].as_ref().unwrap();
+        assert!(matches!(alice[..], [Outcome::Pass(_), Outcome::Pass(_)]));
+        let bob = rows[1].as_ref().unwrap();
+        assert!(matches!(bob[0], Outcome::Pass(_)));
+        assert!(
+            matches!(&bob[1], Outcome::Fail { answer, expected, .. } if answer == "7" && expected == "6")
+        );
+        let carol = rows[2].as_ref().unwrap();
+        assert!(matches!(&carol[0], Outcome::Unchecked { answer, .. } if answer == "5"));
+        let dave = rows[3].as_ref().unwrap();
+        assert!(matches!(dave[..], [Outcome::Error(_), Outcome::Error(_)]));
+        assert_eq!(rows[4], None);
+        // bob's undated input is only for 2022, and erin's error page isn't an input at all
+        assert_eq!(users[1].input(PuzzleId { year: 2023, day: 1 }), None);
+        fs::write(dir.join("erin/input03.txt"), "<!DOCTYPE html>").unwrap();
+        assert_eq!(users[4].input(PuzzleId { year: 2022, day: 3 }), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
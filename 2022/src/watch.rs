@@ -0,0 +1,244 @@
+use crate::puzzle::PuzzleId;
+use anyhow::{Context, Result};
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use yansi::Paint;
+
+pub(crate) struct Options {
+    // The crate that watch rebuilds. The running binary can't reload itself, so each check builds
+    // and runs the current sources with cargo.
+    pub(crate) crate_dir: PathBuf,
+    pub(crate) input_dir: PathBuf,
+    // Solve the example from the puzzle description rather than the real input
+    pub(crate) sample: bool,
+    pub(crate) release: bool,
+}
+
+// Check the puzzle, and then check it again whenever a source file or one of its input or sample
+// files changes. Runs until interrupted.
+pub(crate) fn watch(id: PuzzleId, mut options: Options) -> Result<()> {
+    let src = options.crate_dir.join("src");
+    let inputs = options.input_dir.join(id.year.to_string());
+    fs::create_dir_all(&inputs)
+        .with_context(|| format!("Unable to create {}", inputs.display()))?;
+    // Cargo runs in the crate directory, so a relative input directory would be the wrong one
+    options.input_dir = fs::canonicalize(&options.input_dir)?;
+
+    let (tx, rx) = mpsc::channel();
+    // Editors often write a file in several steps, so wait for them to settle
+    let mut debouncer = new_debouncer(Duration::from_millis(200), tx)?;
+    debouncer.watcher().watch(&src, RecursiveMode::Recursive)?;
+    debouncer
+        .watcher()
+        .watch(&inputs, RecursiveMode::NonRecursive)?;
+
+    check(id, &options);
+    for events in rx {
+        if events?.iter().any(|event| is_relevant(&event.path, id)) {
+            check(id, &options);
+        }
+    }
+    Ok(())
+}
+
+// Rust sources, and the puzzle's own input and sample files. Editor backups and swap files
+// (`day17.rs~`, `.day17.rs.swp`) don't count.
+fn is_relevant(path: &Path, id: PuzzleId) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    name.ends_with(".rs")
+        || name == format!("input{:02}.txt", id.day)
+        || name.starts_with(&format!("sample{:02}", id.day)) && name.ends_with(".txt")
+}
+
+// Run the puzzle's tests and then solve it, and redraw the summary
+fn check(id: PuzzleId, options: &Options) {
+    clear_screen();
+    println!(
+        "Watching day {} ({}), press Ctrl-C to stop",
+        id.day,
+        Paint::new("checking").dimmed()
+    );
+    let start = Instant::now();
+    let tests = cargo(options, &["test", "--", &format!("day{:02}::", id.day)]);
+    let selector = id.to_string();
+    // The input directory is passed as it is, since it needn't be valid UTF-8
+    let mut run_args: Vec<&OsStr> = vec![
+        "run".as_ref(),
+        "-q".as_ref(),
+        "--".as_ref(),
+        "--input-dir".as_ref(),
+        options.input_dir.as_os_str(),
+        "run".as_ref(),
+        selector.as_ref(),
+    ];
+    if options.sample {
+        run_args.push("--sample".as_ref());
+    }
+    // Don't bother solving it if it doesn't build
+    let run = match &tests {
+        Ok(tests) if tests.built => Some(cargo(options, &run_args)),
+        _ => None,
+    };
+
+    clear_screen();
+    println!(
+        "Watching day {}, press Ctrl-C to stop (checked in {:.1} s)",
+        id.day,
+        start.elapsed().as_secs_f64()
+    );
+    println!();
+    match tests {
+        Ok(tests) => print_tests(&tests),
+        Err(e) => println!("{} {:#}", Paint::red('!'), e),
+    }
+    match run {
+        Some(Ok(run)) if run.success => {
+            for line in run.stdout.lines() {
+                println!("{}", line);
+            }
+        }
+        Some(Ok(run)) => print_errors(&run.stderr),
+        Some(Err(e)) => println!("{} {:#}", Paint::red('!'), e),
+        None => {}
+    }
+}
+
+fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+}
+
+// What a cargo command printed
+struct CargoOutput {
+    success: bool,
+    // Whether it got as far as running anything
+    built: bool,
+    stdout: String,
+    stderr: String,
+}
+
+fn cargo<S: AsRef<OsStr>>(options: &Options, args: &[S]) -> Result<CargoOutput> {
+    let mut command = Command::new(env!("CARGO"));
+    command.current_dir(&options.crate_dir).arg(&args[0]);
+    if options.release {
+        command.arg("--release");
+    }
+    let output = command
+        .args(&args[1..])
+        .output()
+        .context("Unable to run cargo")?;
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    Ok(CargoOutput {
+        success: output.status.success(),
+        built: !stderr
+            .lines()
+            .any(|l| l.starts_with("error: could not compile")),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr,
+    })
+}
+
+fn print_tests(tests: &CargoOutput) {
+    if !tests.built {
+        print_errors(&tests.stderr);
+        return;
+    }
+    let results = test_results(&tests.stdout);
+    let failed: Vec<&str> = results
+        .iter()
+        .filter(|(_, passed)| !passed)
+        .map(|(name, _)| *name)
+        .collect();
+    let glyph = match failed.len() {
+        0 => Paint::yellow('*'),
+        _ => Paint::red('x'),
+    };
+    println!(
+        "{} tests: {} passed, {} failed",
+        glyph,
+        results.len() - failed.len(),
+        failed.len()
+    );
+    for name in failed {
+        println!("  {} {}", Paint::red('x'), name);
+    }
+    println!();
+}
+
+// The name of each test that ran, and whether it passed
+fn test_results(stdout: &str) -> Vec<(&str, bool)> {
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("test "))
+        .filter_map(|line| line.rsplit_once(" ... "))
+        .filter_map(|(name, result)| match result {
+            "ok" => Some((name, true)),
+            "FAILED" => Some((name, false)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Just the compiler errors and panics, with where they happened
+fn print_errors(stderr: &str) {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .filter(|l| {
+            l.starts_with("error")
+                || l.starts_with("Error: ")
+                || l.trim_start().starts_with("--> ")
+                || l.contains("panicked at")
+        })
+        .filter(|l| !l.starts_with("error: could not compile"))
+        .take(20)
+        .collect();
+    for line in lines {
+        println!("{} {}", Paint::red('!'), line);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::puzzle::PuzzleId;
+    use crate::watch::{is_relevant, test_results};
+    use std::path::Path;
+
+    #[test]
+    fn is_relevant_test() {
+        let id = PuzzleId { year: 2022, day: 7 };
+        let relevant = |path| is_relevant(Path::new(path), id);
+        assert!(relevant("/src/day07.rs"));
+        assert!(relevant("/src/day08.rs"));
+        assert!(relevant("/inputs/2022/input07.txt"));
+        assert!(relevant("/inputs/2022/sample07-answers.txt"));
+        assert!(!relevant("/inputs/2022/input08.txt"));
+        assert!(!relevant("/src/day07.rs~"));
+        assert!(!relevant("/src/.day07.rs.swp"));
+    }
+
+    #[test]
+    fn test_results_test() {
+        let stdout = "
+running 3 tests
+test day07::test::part1_test ... ok
+test day07::test::part2_test ... FAILED
+test day07::test::real_input ... ignored
+
+failures:
+";
+        assert_eq!(
+            test_results(stdout),
+            vec![
+                ("day07::test::part1_test", true),
+                ("day07::test::part2_test", false)
+            ]
+        );
+    }
+}